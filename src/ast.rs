@@ -1,8 +1,17 @@
+use crate::bigint::BigInt;
+use crate::token::Span;
+
 // Program
 pub struct Program {
     statements: Vec<StatementNode>,
 }
 
+impl Default for Program {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Program {
     pub fn new() -> Self {
         Program {
@@ -33,37 +42,112 @@ pub enum StatementNode {
     LetStatement {
         identifier: Box<ExpressionNode>,
         value: Box<ExpressionNode>,
+        span: Span,
+    },
+    // 代入も宣言しなおしもできない束縛
+    ConstStatement {
+        identifier: Box<ExpressionNode>,
+        value: Box<ExpressionNode>,
+        span: Span,
     },
     ReturnStatement {
         return_value: Box<ExpressionNode>,
+        span: Span,
     },
     ExpressionStatement {
         expression: Box<ExpressionNode>,
+        span: Span,
     },
     BlockStatement {
         statements: Vec<StatementNode>,
+        span: Span,
+    },
+    WhileStatement {
+        condition: Box<ExpressionNode>,
+        body: Box<StatementNode>,
+        span: Span,
+    },
+    // for (value in iterable) と for (key, value in iterable)
+    ForStatement {
+        key: Option<Box<ExpressionNode>>,
+        value: Box<ExpressionNode>,
+        iterable: Box<ExpressionNode>,
+        body: Box<StatementNode>,
+        span: Span,
+    },
+    BreakStatement {
+        span: Span,
+    },
+    ContinueStatement {
+        span: Span,
     },
 }
 
 impl StatementNode {
+    pub fn span(&self) -> Span {
+        match &self {
+            StatementNode::LetStatement { span, .. }
+            | StatementNode::ConstStatement { span, .. }
+            | StatementNode::ReturnStatement { span, .. }
+            | StatementNode::ExpressionStatement { span, .. }
+            | StatementNode::BlockStatement { span, .. }
+            | StatementNode::WhileStatement { span, .. }
+            | StatementNode::ForStatement { span, .. }
+            | StatementNode::BreakStatement { span }
+            | StatementNode::ContinueStatement { span } => *span,
+        }
+    }
+
     pub fn literal(&self) -> String {
         match &self {
-            StatementNode::LetStatement { identifier, value } => {
+            StatementNode::LetStatement {
+                identifier, value, ..
+            } => {
                 format!("let {} = {};", identifier.literal(), value.literal())
             }
-            StatementNode::ReturnStatement { return_value } => {
+            StatementNode::ConstStatement {
+                identifier, value, ..
+            } => {
+                format!("const {} = {};", identifier.literal(), value.literal())
+            }
+            StatementNode::ReturnStatement { return_value, .. } => {
                 format!("return {};", return_value.literal())
             }
-            StatementNode::ExpressionStatement { expression } => {
+            StatementNode::ExpressionStatement { expression, .. } => {
                 format!("{};", expression.literal())
             }
-            StatementNode::BlockStatement { statements } => {
+            StatementNode::BlockStatement { statements, .. } => {
                 let mut literal = String::new();
                 for statement in statements {
                     literal = format!("{} {}", literal, statement.literal());
                 }
                 format!("{{{} }}", literal)
             }
+            StatementNode::WhileStatement {
+                condition, body, ..
+            } => {
+                format!("while {} {}", condition.literal(), body.literal())
+            }
+            StatementNode::ForStatement {
+                key,
+                value,
+                iterable,
+                body,
+                ..
+            } => {
+                let variables = match key {
+                    Some(key) => format!("{}, {}", key.literal(), value.literal()),
+                    None => value.literal(),
+                };
+                format!(
+                    "for {} in {} {}",
+                    variables,
+                    iterable.literal(),
+                    body.literal()
+                )
+            }
+            StatementNode::BreakStatement { .. } => "break;".to_string(),
+            StatementNode::ContinueStatement { .. } => "continue;".to_string(),
         }
     }
 }
@@ -81,98 +165,282 @@ pub enum PrefixOperatorType {
     Bang,
 }
 
+impl PrefixOperatorType {
+    pub fn literal(&self) -> &'static str {
+        match self {
+            PrefixOperatorType::Bang => "!",
+            PrefixOperatorType::Minus => "-",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum InfixOperatorType {
     Plus,
     Minus,
     Asterisk,
     Slash,
+    Percent,
     Eq,
     NotEq,
     Lt,
     Gt,
+    LtEq,
+    GtEq,
+}
+
+impl InfixOperatorType {
+    pub fn literal(&self) -> &'static str {
+        match self {
+            InfixOperatorType::Plus => "+",
+            InfixOperatorType::Minus => "-",
+            InfixOperatorType::Asterisk => "*",
+            InfixOperatorType::Slash => "/",
+            InfixOperatorType::Percent => "%",
+            InfixOperatorType::Eq => "==",
+            InfixOperatorType::NotEq => "!=",
+            InfixOperatorType::Gt => ">",
+            InfixOperatorType::Lt => "<",
+            InfixOperatorType::GtEq => ">=",
+            InfixOperatorType::LtEq => "<=",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum AssignOperatorType {
+    Assign,
+    PlusAssign,
+    MinusAssign,
+}
+
+impl AssignOperatorType {
+    pub fn literal(&self) -> &'static str {
+        match self {
+            AssignOperatorType::Assign => "=",
+            AssignOperatorType::PlusAssign => "+=",
+            AssignOperatorType::MinusAssign => "-=",
+        }
+    }
+
+    // `x += y` は `x = x + y` として計算する
+    pub fn infix_operator(&self) -> Option<InfixOperatorType> {
+        match self {
+            AssignOperatorType::Assign => None,
+            AssignOperatorType::PlusAssign => Some(InfixOperatorType::Plus),
+            AssignOperatorType::MinusAssign => Some(InfixOperatorType::Minus),
+        }
+    }
+}
+
+// 右辺を評価するかどうかが左辺で決まるので InfixOperatorType とは分ける
+#[derive(Clone)]
+pub enum LogicalOperatorType {
+    And,
+    Or,
+}
+
+impl LogicalOperatorType {
+    pub fn literal(&self) -> &'static str {
+        match self {
+            LogicalOperatorType::And => "&&",
+            LogicalOperatorType::Or => "||",
+        }
+    }
 }
 
 #[derive(Clone)]
 pub enum ExpressionNode {
     Identifier {
         literal: Vec<char>,
+        span: Span,
     },
+    // 整数 literal は parse した値を持つ. i64 に収まらなければ BigInteger
     Integer {
+        value: i64,
+        span: Span,
+    },
+    BigInteger {
+        value: BigInt,
+        span: Span,
+    },
+    Float {
+        literal: Vec<char>,
+        span: Span,
+    },
+    StringLiteral {
         literal: Vec<char>,
+        span: Span,
+    },
+    ArrayLiteral {
+        elements: Vec<Box<ExpressionNode>>,
+        span: Span,
+    },
+    HashLiteral {
+        pairs: Vec<(Box<ExpressionNode>, Box<ExpressionNode>)>,
+        span: Span,
     },
     Boolean {
         boolean_type: BooleanType,
+        span: Span,
     },
     PrefixOperator {
         operator_type: PrefixOperatorType,
         right: Box<ExpressionNode>,
+        span: Span,
     },
     InfixOperator {
         operator_type: InfixOperatorType,
         left: Box<ExpressionNode>,
         right: Box<ExpressionNode>,
+        span: Span,
+    },
+    LogicalOperator {
+        operator_type: LogicalOperatorType,
+        left: Box<ExpressionNode>,
+        right: Box<ExpressionNode>,
+        span: Span,
+    },
+    // target は Identifier か, Identifier から始まる IndexExpression
+    Assign {
+        operator_type: AssignOperatorType,
+        target: Box<ExpressionNode>,
+        value: Box<ExpressionNode>,
+        span: Span,
     },
     IfExpression {
         condition: Box<ExpressionNode>,
         consequence: Box<StatementNode>,
         alternative: Option<Box<StatementNode>>,
+        span: Span,
     },
     FunctionLiteral {
         parameters: Vec<Box<ExpressionNode>>,
         body: Box<StatementNode>,
+        span: Span,
     },
     CallExpression {
         function: Box<ExpressionNode>,
         arguments: Vec<Box<ExpressionNode>>,
+        span: Span,
+    },
+    IndexExpression {
+        left: Box<ExpressionNode>,
+        index: Box<ExpressionNode>,
+        span: Span,
     },
 }
 
 impl ExpressionNode {
+    pub fn span(&self) -> Span {
+        match &self {
+            ExpressionNode::Identifier { span, .. }
+            | ExpressionNode::Integer { span, .. }
+            | ExpressionNode::BigInteger { span, .. }
+            | ExpressionNode::Float { span, .. }
+            | ExpressionNode::StringLiteral { span, .. }
+            | ExpressionNode::ArrayLiteral { span, .. }
+            | ExpressionNode::HashLiteral { span, .. }
+            | ExpressionNode::Boolean { span, .. }
+            | ExpressionNode::PrefixOperator { span, .. }
+            | ExpressionNode::InfixOperator { span, .. }
+            | ExpressionNode::LogicalOperator { span, .. }
+            | ExpressionNode::Assign { span, .. }
+            | ExpressionNode::IfExpression { span, .. }
+            | ExpressionNode::FunctionLiteral { span, .. }
+            | ExpressionNode::CallExpression { span, .. }
+            | ExpressionNode::IndexExpression { span, .. } => *span,
+        }
+    }
+
     pub fn literal(&self) -> String {
         match &self {
-            ExpressionNode::Identifier { literal } => literal.iter().collect::<String>(),
-            ExpressionNode::Integer { literal } => literal.iter().collect::<String>(),
-            ExpressionNode::Boolean { boolean_type } => match boolean_type {
+            ExpressionNode::Identifier { literal, .. } => literal.iter().collect::<String>(),
+            ExpressionNode::Integer { value, .. } => value.to_string(),
+            ExpressionNode::BigInteger { value, .. } => value.to_string(),
+            ExpressionNode::Float { literal, .. } => literal.iter().collect::<String>(),
+            ExpressionNode::StringLiteral { literal, .. } => {
+                // lexer で読み戻せるように escape して引用符で囲む
+                let mut escaped = "\"".to_string();
+                for c in literal.iter() {
+                    match c {
+                        '"' => escaped.push_str("\\\""),
+                        '\\' => escaped.push_str("\\\\"),
+                        '\n' => escaped.push_str("\\n"),
+                        '\t' => escaped.push_str("\\t"),
+                        '\r' => escaped.push_str("\\r"),
+                        _ => escaped.push(*c),
+                    }
+                }
+                escaped.push('"');
+                escaped
+            }
+            ExpressionNode::ArrayLiteral { elements, .. } => {
+                let elements: Vec<String> = elements.iter().map(|e| e.literal()).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            ExpressionNode::HashLiteral { pairs, .. } => {
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key.literal(), value.literal()))
+                    .collect();
+                format!("{{{}}}", pairs.join(", "))
+            }
+            ExpressionNode::Boolean { boolean_type, .. } => match boolean_type {
                 BooleanType::True => "true".to_string(),
                 BooleanType::False => "false".to_string(),
             },
             ExpressionNode::PrefixOperator {
                 operator_type,
                 right,
+                ..
             } => {
-                let operator_literal = match operator_type {
-                    PrefixOperatorType::Bang => "!".to_string(),
-                    PrefixOperatorType::Minus => "-".to_string(),
-                };
-                format!("({}{})", operator_literal, right.literal())
+                format!("({}{})", operator_type.literal(), right.literal())
             }
             ExpressionNode::InfixOperator {
                 operator_type,
                 left,
                 right,
+                ..
+            } => {
+                format!(
+                    "({} {} {})",
+                    left.literal(),
+                    operator_type.literal(),
+                    right.literal()
+                )
+            }
+            ExpressionNode::LogicalOperator {
+                operator_type,
+                left,
+                right,
+                ..
             } => {
-                let operator_literal = match operator_type {
-                    InfixOperatorType::Plus => "+".to_string(),
-                    InfixOperatorType::Minus => "-".to_string(),
-                    InfixOperatorType::Asterisk => "*".to_string(),
-                    InfixOperatorType::Slash => "/".to_string(),
-                    InfixOperatorType::Eq => "==".to_string(),
-                    InfixOperatorType::NotEq => "!=".to_string(),
-                    InfixOperatorType::Gt => ">".to_string(),
-                    InfixOperatorType::Lt => "<".to_string(),
-                };
                 format!(
                     "({} {} {})",
                     left.literal(),
-                    operator_literal,
+                    operator_type.literal(),
                     right.literal()
                 )
             }
+            ExpressionNode::Assign {
+                operator_type,
+                target,
+                value,
+                ..
+            } => {
+                format!(
+                    "({} {} {})",
+                    target.literal(),
+                    operator_type.literal(),
+                    value.literal()
+                )
+            }
             ExpressionNode::IfExpression {
                 condition,
                 consequence,
                 alternative,
+                ..
             } => {
                 let literal = format!("if {} {}", condition.literal(), consequence.literal());
                 match &alternative {
@@ -180,10 +448,12 @@ impl ExpressionNode {
                     None => literal,
                 }
             }
-            ExpressionNode::FunctionLiteral { parameters, body } => {
+            ExpressionNode::FunctionLiteral {
+                parameters, body, ..
+            } => {
                 let mut parameters_literal = "".to_string();
                 for parameter in parameters.iter() {
-                    if parameters_literal == "" {
+                    if parameters_literal.is_empty() {
                         parameters_literal = parameter.literal();
                     } else {
                         parameters_literal =
@@ -196,10 +466,11 @@ impl ExpressionNode {
             ExpressionNode::CallExpression {
                 function,
                 arguments,
+                ..
             } => {
                 let mut arguments_literal = "".to_string();
                 for argument in arguments.iter() {
-                    if arguments_literal == "" {
+                    if arguments_literal.is_empty() {
                         arguments_literal = argument.literal();
                     } else {
                         arguments_literal =
@@ -209,6 +480,9 @@ impl ExpressionNode {
 
                 format!("{}({})", function.literal(), arguments_literal)
             }
+            ExpressionNode::IndexExpression { left, index, .. } => {
+                format!("({}[{}])", left.literal(), index.literal())
+            }
         }
     }
 }
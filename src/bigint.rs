@@ -0,0 +1,451 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// 任意精度の整数. i64 に収まらない整数演算の結果を表す
+// magnitude は 2^32 進数の little endian で, 末尾に 0 を持たない (0 は空の Vec)
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        BigInt {
+            negative: false,
+            magnitude: Vec::new(),
+        }
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        let mut magnitude = value.unsigned_abs();
+        let mut digits = Vec::new();
+        while magnitude > 0 {
+            digits.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        BigInt::new(value < 0, digits)
+    }
+
+    // 10 進数の文字列 (先頭に `-` を許す) を読む
+    pub fn parse(literal: &str) -> Option<Self> {
+        let (negative, digits) = match literal.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, literal),
+        };
+        let value = BigInt::parse_radix(digits, 10)?;
+        Some(BigInt::new(negative, value.magnitude))
+    }
+
+    // radix 進数の数字の列 (符号なし) を読む. radix は 2 から 36
+    pub fn parse_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut magnitude = Vec::new();
+        for c in digits.chars() {
+            let digit = c.to_digit(radix)?;
+            mul_small(&mut magnitude, radix);
+            add_small(&mut magnitude, digit);
+        }
+        Some(BigInt::new(false, magnitude))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &digit| (acc << 32) | digit as u64);
+        if self.negative {
+            if magnitude <= i64::MAX as u64 + 1 {
+                Some((magnitude as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    // 最も近い f64. 大きすぎれば無限大になる
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    // 0 に向かって切り捨てる (i64 の `/` と同じ). 0 で割ると None
+    pub fn checked_div(&self, other: &BigInt) -> Option<BigInt> {
+        if other.is_zero() {
+            return None;
+        }
+        let quotient = div_mag(&self.magnitude, &other.magnitude);
+        Some(BigInt::new(self.negative != other.negative, quotient))
+    }
+
+    // checked_div と組になる余り. 符号は self と同じになる
+    pub fn checked_rem(&self, other: &BigInt) -> Option<BigInt> {
+        let quotient = self.checked_div(other)?;
+        Some(self - &(&quotient * other))
+    }
+
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        // -0 は作らない
+        let negative = negative && !magnitude.is_empty();
+        BigInt {
+            negative,
+            magnitude,
+        }
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_mag(&self.magnitude, &other.magnitude));
+        }
+        // 符号が違えば, 絶対値の大きい方から小さい方を引く
+        match cmp_mag(&self.magnitude, &other.magnitude) {
+            Ordering::Less => {
+                BigInt::new(other.negative, sub_mag(&other.magnitude, &self.magnitude))
+            }
+            _ => BigInt::new(self.negative, sub_mag(&self.magnitude, &other.magnitude)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &(-other)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            mul_mag(&self.magnitude, &other.magnitude),
+        )
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_mag(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Token::Int などの Debug 表示でも 10 進数で見えるようにする
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // 10^9 ずつ割って下の桁から取り出す
+        let mut magnitude = self.magnitude.clone();
+        let mut chunks = Vec::new();
+        while !magnitude.is_empty() {
+            chunks.push(div_small(&mut magnitude, 1_000_000_000));
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap_or(0))?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+fn cmp_mag(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_mag(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..left.len().max(right.len()) {
+        let sum = *left.get(i).unwrap_or(&0) as u64 + *right.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+// left >= right でなければならない
+fn sub_mag(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (i, &digit) in left.iter().enumerate() {
+        let mut difference = digit as i64 - *right.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = if difference < 0 {
+            difference += 1 << 32;
+            1
+        } else {
+            0
+        };
+        result.push(difference as u32);
+    }
+    result
+}
+
+fn mul_mag(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; left.len() + right.len()];
+    for (i, &l) in left.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &r) in right.iter().enumerate() {
+            let product = l as u64 * r as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + right.len()] = carry as u32;
+    }
+    result
+}
+
+// 1 bit ずつ引き算する筆算. 商の絶対値を返す
+fn div_mag(dividend: &[u32], divisor: &[u32]) -> Vec<u32> {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for i in (0..dividend.len() * 32).rev() {
+        // remainder = remainder * 2 + (dividend の i bit 目)
+        mul_small(&mut remainder, 2);
+        add_small(&mut remainder, (dividend[i / 32] >> (i % 32)) & 1);
+        if cmp_mag(&remainder, divisor) != Ordering::Less {
+            remainder = sub_mag(&remainder, divisor);
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+            quotient[i / 32] |= 1 << (i % 32);
+        }
+    }
+    quotient
+}
+
+fn mul_small(magnitude: &mut Vec<u32>, factor: u32) {
+    let mut carry = 0u64;
+    for digit in magnitude.iter_mut() {
+        let product = *digit as u64 * factor as u64 + carry;
+        *digit = product as u32;
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn add_small(magnitude: &mut Vec<u32>, addend: u32) {
+    let mut carry = addend as u64;
+    for digit in magnitude.iter_mut() {
+        if carry == 0 {
+            return;
+        }
+        let sum = *digit as u64 + carry;
+        *digit = sum as u32;
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+// magnitude を divisor で割り, 余りを返す
+fn div_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for digit in magnitude.iter_mut().rev() {
+        let current = (remainder << 32) | *digit as u64;
+        *digit = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    remainder as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn big(literal: &str) -> BigInt {
+        BigInt::parse(literal).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display_test() {
+        let tests = vec![
+            "0",
+            "1",
+            "-1",
+            "4294967296",
+            "9223372036854775808",
+            "-9223372036854775809",
+            "123456789012345678901234567890",
+            "1000000000000000000",
+        ];
+        for test in tests {
+            assert_eq!(big(test).to_string(), test);
+        }
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("007").to_string(), "7");
+        assert_eq!(BigInt::parse(""), None);
+        assert_eq!(BigInt::parse("-"), None);
+        assert_eq!(BigInt::parse("12a"), None);
+    }
+
+    #[test]
+    fn i64_conversion_test() {
+        for value in [0, 1, -1, i64::MAX, i64::MIN, 4294967296, -4294967296] {
+            assert_eq!(BigInt::from_i64(value).to_i64(), Some(value));
+            assert_eq!(BigInt::from_i64(value).to_string(), value.to_string());
+        }
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+        assert_eq!(big("18446744073709551616").to_i64(), None);
+        assert_eq!(BigInt::parse_radix("ff", 16), Some(BigInt::from_i64(255)));
+        assert_eq!(
+            BigInt::parse_radix("10000000000000000", 16)
+                .unwrap()
+                .to_string(),
+            "18446744073709551616"
+        );
+        assert_eq!(BigInt::parse_radix("102", 2), None);
+        assert_eq!(
+            big("-18446744073709551616").to_f64(),
+            -18446744073709551616.0
+        );
+    }
+
+    #[test]
+    fn arithmetic_test() {
+        let max = BigInt::from_i64(i64::MAX);
+        let two = BigInt::from_i64(2);
+        assert_eq!((&two * &max).to_string(), "18446744073709551614");
+        assert_eq!((&max + &max).to_string(), "18446744073709551614");
+        assert_eq!((&(&max + &two) - &two), max);
+        assert_eq!((&(-&max) - &two).to_string(), "-9223372036854775809");
+        assert_eq!((&big("5") - &big("8")).to_string(), "-3");
+        assert_eq!((&big("-5") + &big("8")).to_string(), "3");
+        assert_eq!((&big("-5") + &big("5")).to_string(), "0");
+        assert_eq!(
+            (&big("-123456789012345678901") * &big("98765432109876543210")).to_string(),
+            "-12193263113702179522473403443222511812210"
+        );
+    }
+
+    #[test]
+    fn division_test() {
+        let tests = vec![
+            ("18446744073709551614", "2", "9223372036854775807"),
+            (
+                "123456789012345678901234567890",
+                "987654321",
+                "124999998873437499901",
+            ),
+            ("-7", "2", "-3"),
+            ("7", "-2", "-3"),
+            ("-7", "-2", "3"),
+            ("1", "123456789012345678901", "0"),
+        ];
+        for (dividend, divisor, quotient) in tests {
+            assert_eq!(
+                big(dividend)
+                    .checked_div(&big(divisor))
+                    .unwrap()
+                    .to_string(),
+                quotient
+            );
+        }
+        assert_eq!(big("1").checked_div(&BigInt::zero()), None);
+
+        let tests = vec![
+            ("-7", "2", "-1"),
+            ("7", "-2", "1"),
+            ("123456789012345678901234567890", "987654321", "574845669"),
+        ];
+        for (dividend, divisor, remainder) in tests {
+            assert_eq!(
+                big(dividend)
+                    .checked_rem(&big(divisor))
+                    .unwrap()
+                    .to_string(),
+                remainder
+            );
+        }
+        assert_eq!(big("1").checked_rem(&BigInt::zero()), None);
+    }
+
+    #[test]
+    fn ordering_test() {
+        let mut values = [
+            big("10"),
+            big("-10"),
+            big("0"),
+            big("-99999999999999999999"),
+            big("99999999999999999999"),
+        ];
+        values.sort();
+        let sorted: Vec<String> = values.iter().map(BigInt::to_string).collect();
+        assert_eq!(
+            sorted,
+            vec![
+                "-99999999999999999999",
+                "-10",
+                "0",
+                "10",
+                "99999999999999999999"
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use crate::bigint::BigInt;
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::object::*;
+
+const BUILTINS: &[(&str, BuiltinFunction)] = &[
+    ("len", len),
+    ("puts", puts),
+    ("first", first),
+    ("last", last),
+    ("rest", rest),
+    ("push", push),
+    ("type", type_of),
+    ("str", str),
+    ("range", range),
+];
+
+// 環境に見つからなかった名前は, 最後に builtin から探す
+pub fn lookup(name: &str) -> Option<Object> {
+    BUILTINS
+        .iter()
+        .find(|(builtin_name, _)| *builtin_name == name)
+        .map(|&(name, function)| Object::Builtin { name, function })
+}
+
+fn len(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    check_arity(arguments, 1)?;
+    let length = match &arguments[0] {
+        Object::String { value } => value.chars().count(),
+        Object::Array { elements } => elements.len(),
+        Object::Hash { pairs } => pairs.len(),
+        Object::Range { start, end } if end <= start => 0,
+        // 両端の差は i64 に収まらないことがある
+        Object::Range { start, end } => {
+            let length = &BigInt::from_i64(*end) - &BigInt::from_i64(*start);
+            return Ok(Object::from_big_int(length));
+        }
+        object => {
+            return Err(invalid_argument(
+                "len",
+                "string, array, hash or range",
+                object,
+            ))
+        }
+    };
+    Ok(Object::Integer {
+        value: length as i64,
+    })
+}
+
+fn puts(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    for argument in arguments {
+        println!("{}", argument.literal());
+    }
+    Ok(Object::Null)
+}
+
+fn first(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    check_arity(arguments, 1)?;
+    let elements = expect_array("first", &arguments[0])?;
+    Ok(elements.first().cloned().unwrap_or(Object::Null))
+}
+
+fn last(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    check_arity(arguments, 1)?;
+    let elements = expect_array("last", &arguments[0])?;
+    Ok(elements.last().cloned().unwrap_or(Object::Null))
+}
+
+// 先頭を除いた新しい配列を返す. 空の配列には null を返す
+fn rest(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    check_arity(arguments, 1)?;
+    let elements = expect_array("rest", &arguments[0])?;
+    if elements.is_empty() {
+        return Ok(Object::Null);
+    }
+    Ok(Object::Array {
+        elements: elements[1..].to_vec(),
+    })
+}
+
+// 元の配列は変更せず, 末尾に追加した新しい配列を返す
+fn push(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    check_arity(arguments, 2)?;
+    let mut elements = expect_array("push", &arguments[0])?.clone();
+    elements.push(arguments[1].clone());
+    Ok(Object::Array { elements })
+}
+
+fn type_of(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    check_arity(arguments, 1)?;
+    Ok(Object::String {
+        value: arguments[0].type_name().to_string(),
+    })
+}
+
+fn str(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    check_arity(arguments, 1)?;
+    Ok(Object::String {
+        value: arguments[0].literal(),
+    })
+}
+
+// range(end) は 0 から, range(start, end) は start から end の手前までの整数の列
+fn range(arguments: &[Object]) -> Result<Object, RuntimeError> {
+    let (start, end) = match arguments {
+        [end] => (0, expect_integer("range", end)?),
+        [start, end] => (
+            expect_integer("range", start)?,
+            expect_integer("range", end)?,
+        ),
+        _ => {
+            return Err(RuntimeError::new(RuntimeErrorKind::ArityMismatch {
+                expected: if arguments.is_empty() { 1 } else { 2 },
+                actual: arguments.len(),
+            }))
+        }
+    };
+    Ok(Object::Range { start, end })
+}
+
+fn check_arity(arguments: &[Object], expected: usize) -> Result<(), RuntimeError> {
+    if arguments.len() == expected {
+        Ok(())
+    } else {
+        Err(RuntimeError::new(RuntimeErrorKind::ArityMismatch {
+            expected,
+            actual: arguments.len(),
+        }))
+    }
+}
+
+fn expect_array<'a>(
+    function: &'static str,
+    object: &'a Object,
+) -> Result<&'a Vec<Object>, RuntimeError> {
+    match object {
+        Object::Array { elements } => Ok(elements),
+        _ => Err(invalid_argument(function, "array", object)),
+    }
+}
+
+fn expect_integer(function: &'static str, object: &Object) -> Result<i64, RuntimeError> {
+    match object {
+        Object::Integer { value } => Ok(*value),
+        Object::BigInteger { value } => {
+            Err(RuntimeError::new(RuntimeErrorKind::IntegerOutOfRange {
+                function,
+                value: value.to_string(),
+            }))
+        }
+        _ => Err(invalid_argument(function, "integer", object)),
+    }
+}
+
+fn invalid_argument(
+    function: &'static str,
+    expected: &'static str,
+    actual: &Object,
+) -> RuntimeError {
+    RuntimeError::new(RuntimeErrorKind::InvalidArgument {
+        function,
+        expected,
+        actual: actual.type_name(),
+    })
+}
@@ -0,0 +1,295 @@
+use crate::error::{Error, LexError, Note, ParseError, RuntimeError};
+use crate::token::Span;
+
+// rustc 風にエラー箇所のソースを引用して表示するためのエラー情報
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<Note>,
+}
+
+impl Diagnostic {
+    pub fn from_error(error: &Error) -> Vec<Diagnostic> {
+        match error {
+            Error::Io { .. } => vec![Diagnostic {
+                message: error.to_string(),
+                span: None,
+                notes: Vec::new(),
+            }],
+            Error::Lex(error) => vec![Diagnostic::from(error)],
+            Error::Parse(errors) => errors.iter().map(Diagnostic::from).collect(),
+            Error::Runtime(error) => vec![Diagnostic::from(error)],
+        }
+    }
+
+    // source は span が指しているソース全体, name はファイル名などの表示名
+    pub fn render(&self, source: &str, name: &str, color: bool) -> String {
+        let style = Style { color };
+        let width = self
+            .span
+            .iter()
+            .chain(self.notes.iter().map(|note| &note.span))
+            .map(|span| span.start.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        let mut rendered = format!(
+            "{}: {}\n",
+            style.paint(RED, "error"),
+            style.paint(BOLD, &self.message)
+        );
+        if let Some(span) = self.span {
+            rendered.push_str(&format!(
+                "{}{} {}:{}\n",
+                " ".repeat(width),
+                style.paint(BLUE, "-->"),
+                name,
+                span.start
+            ));
+            render_snippet(
+                &mut rendered,
+                source,
+                span,
+                "",
+                Marker::Primary,
+                width,
+                &style,
+            );
+        }
+        for note in &self.notes {
+            rendered.push_str(&format!(
+                "{}{} {}:{}\n",
+                " ".repeat(width),
+                style.paint(BLUE, ":::"),
+                name,
+                note.span.start
+            ));
+            render_snippet(
+                &mut rendered,
+                source,
+                note.span,
+                &note.message,
+                Marker::Secondary,
+                width,
+                &style,
+            );
+        }
+        rendered
+    }
+}
+
+impl From<&LexError> for Diagnostic {
+    fn from(error: &LexError) -> Self {
+        Diagnostic {
+            message: error.kind.to_string(),
+            span: Some(error.span),
+            notes: Vec::new(),
+        }
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        Diagnostic {
+            message: error.kind.to_string(),
+            span: Some(error.span),
+            notes: error.notes.clone(),
+        }
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        Diagnostic {
+            message: error.kind.to_string(),
+            span: error.span,
+            notes: error.notes.clone(),
+        }
+    }
+}
+
+// error に含まれるすべての Diagnostic を続けて描画する
+pub fn render_error(error: &Error, source: &str, name: &str, color: bool) -> String {
+    Diagnostic::from_error(error)
+        .iter()
+        .map(|diagnostic| diagnostic.render(source, name, color))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+enum Marker {
+    Primary,
+    Secondary,
+}
+
+//   |
+// 2 |     x + ;
+//   |         ^ label
+fn render_snippet(
+    rendered: &mut String,
+    source: &str,
+    span: Span,
+    label: &str,
+    marker: Marker,
+    width: usize,
+    style: &Style,
+) {
+    let line = match source
+        .lines()
+        .nth((span.start.line as usize).saturating_sub(1))
+    {
+        Some(line) => line,
+        None => return,
+    };
+    let gutter = style.paint(BLUE, "|");
+
+    // 複数行にまたがる span は最初の行の末尾まで下線を引く
+    let line_length = line.chars().count() as u32;
+    let start_column = span.start.column.min(line_length + 1);
+    let end_column = if span.end.line == span.start.line {
+        span.end.column
+    } else {
+        line_length + 1
+    };
+    let underline_length = end_column.saturating_sub(start_column).max(1) as usize;
+
+    // tab の幅がずれないよう, 元の行の tab はそのまま残す
+    let padding: String = line
+        .chars()
+        .take(start_column as usize - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let (marker_char, code) = match marker {
+        Marker::Primary => ('^', RED),
+        Marker::Secondary => ('-', BLUE),
+    };
+    let mut underline = marker_char.to_string().repeat(underline_length);
+    if !label.is_empty() {
+        underline = format!("{} {}", underline, label);
+    }
+
+    rendered.push_str(&format!("{} {}\n", " ".repeat(width), gutter));
+    rendered.push_str(&format!(
+        "{} {} {}\n",
+        style.paint(BLUE, &format!("{:>width$}", span.start.line, width = width)),
+        gutter,
+        line
+    ));
+    rendered.push_str(&format!(
+        "{} {} {}{}\n",
+        " ".repeat(width),
+        gutter,
+        padding,
+        style.paint(code, &underline)
+    ));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn render(source: &str) -> String {
+        let error: Error = match crate::lexer::lex(source) {
+            Err(error) => error.into(),
+            Ok(tokens) => match crate::parser::parse(tokens) {
+                Err(errors) => errors.into(),
+                Ok(program) => {
+                    let mut env =
+                        std::rc::Rc::new(std::cell::RefCell::new(crate::env::Enviroment::new()));
+                    crate::evaluator::Evaluator::new()
+                        .eavl_program(&program, &mut env)
+                        .err()
+                        .unwrap()
+                        .into()
+                }
+            },
+        };
+        render_error(&error, source, "test.mk", false)
+    }
+
+    #[test]
+    fn test_render_lex_error() {
+        let expect = "error: illegal character '@'
+ --> test.mk:1:11
+  |
+1 | let a = 1 @ 2;
+  |           ^
+";
+        assert_eq!(render("let a = 1 @ 2;"), expect);
+    }
+
+    #[test]
+    fn test_render_parse_errors() {
+        let expect = "error: expected expression, found SemiColon
+ --> test.mk:1:9
+  |
+1 | let x = ;
+  |         ^
+
+error: expected RBrace, found Eof
+ --> test.mk:3:6
+  |
+3 | x + 1
+  |      ^
+ ::: test.mk:2:8
+  |
+2 | if (x) {
+  |        - block opened here
+";
+        assert_eq!(render("let x = ;\nif (x) {\nx + 1"), expect);
+    }
+
+    #[test]
+    fn test_render_runtime_error_with_note() {
+        let source = "let add = fn(x, y) { x + y };\n\n\n\n\n\n\n\n\nadd(1);";
+        let expect = "error: wrong number of arguments: expected 2, got 1
+  --> test.mk:10:1
+   |
+10 | add(1);
+   | ^^^^^^
+  ::: test.mk:1:11
+   |
+ 1 | let add = fn(x, y) { x + y };
+   |           ------------------ function defined here with 2 parameters
+";
+        assert_eq!(render(source), expect);
+    }
+
+    #[test]
+    fn test_render_tabs_and_color() {
+        let expect = "\x1b[1;31merror\x1b[0m: \x1b[1midentifier not found: x\x1b[0m
+ \x1b[1;34m-->\x1b[0m test.mk:1:2
+  \x1b[1;34m|\x1b[0m
+\x1b[1;34m1\x1b[0m \x1b[1;34m|\x1b[0m \tx +\ty
+  \x1b[1;34m|\x1b[0m \t\x1b[1;31m^\x1b[0m
+";
+        let source = "\tx +\ty";
+        let program = crate::parser::parse(crate::lexer::lex(source).unwrap()).unwrap();
+        let mut env = std::rc::Rc::new(std::cell::RefCell::new(crate::env::Enviroment::new()));
+        let error = crate::evaluator::Evaluator::new()
+            .eavl_program(&program, &mut env)
+            .err();
+        let actual = Diagnostic::from(&error.unwrap()).render(source, "test.mk", true);
+        assert_eq!(actual, expect);
+    }
+}
@@ -4,11 +4,30 @@ use std::rc::Rc;
 use crate::object::*;
 use std::collections::HashMap;
 
+// 環境に束縛された値. const で宣言されたものは mutable が false
+pub struct Binding {
+    pub value: Object,
+    pub mutable: bool,
+}
+
+// Enviroment::set, set_const, assign が失敗した理由
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssignError {
+    Undeclared,
+    Constant,
+}
+
 pub struct Enviroment {
-    store: HashMap<String, Object>,
+    store: HashMap<String, Binding>,
     outer: Option<Rc<RefCell<Enviroment>>>,
 }
 
+impl Default for Enviroment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Enviroment {
     pub fn new() -> Self {
         Enviroment {
@@ -17,23 +36,70 @@ impl Enviroment {
         }
     }
 
-    pub fn set(&mut self, name: &str, object: Object) {
-        self.store.insert(name.to_string(), object);
+    // この環境に変更できる変数として束縛する. 同じ名前の束縛があれば置き換えるが, const は置き換えない
+    pub fn set(&mut self, name: &str, object: Object) -> Result<(), AssignError> {
+        self.bind(name, object, true)
+    }
+
+    // この環境に変更できない変数として束縛する
+    pub fn set_const(&mut self, name: &str, object: Object) -> Result<(), AssignError> {
+        self.bind(name, object, false)
+    }
+
+    fn bind(&mut self, name: &str, object: Object, mutable: bool) -> Result<(), AssignError> {
+        if self.store.get(name).is_some_and(|binding| !binding.mutable) {
+            return Err(AssignError::Constant);
+        }
+        self.store.insert(
+            name.to_string(),
+            Binding {
+                value: object,
+                mutable,
+            },
+        );
+        Ok(())
     }
 
     pub fn get(&self, name: &str) -> Option<Object> {
-        let value = self.store.get(name).map(|o| (*o).clone().to_owned());
-        if value.is_none() {
-            if self.outer.is_none() {
-                None
-            } else {
-                (self.outer).as_ref().unwrap().borrow().get(name)
+        match self.store.get(name) {
+            Some(binding) => Some(binding.value.clone()),
+            None => match &self.outer {
+                Some(outer) => outer.borrow().get(name),
+                None => None,
+            },
+        }
+    }
+
+    // 外側の環境までたどって見つけた束縛が変更できるか. 見つからなければ None
+    pub fn is_mutable(&self, name: &str) -> Option<bool> {
+        match self.store.get(name) {
+            Some(binding) => Some(binding.mutable),
+            None => match &self.outer {
+                Some(outer) => outer.borrow().is_mutable(name),
+                None => None,
+            },
+        }
+    }
+
+    // 宣言済みの変数を, 外側の環境までたどって見つけたところで更新する
+    pub fn assign(&mut self, name: &str, object: Object) -> Result<(), AssignError> {
+        if let Some(binding) = self.store.get_mut(name) {
+            if !binding.mutable {
+                return Err(AssignError::Constant);
             }
-        } else {
-            value
+            binding.value = object;
+            return Ok(());
+        }
+        match &self.outer {
+            Some(outer) => outer.borrow_mut().assign(name, object),
+            None => Err(AssignError::Undeclared),
         }
     }
 
+    pub fn store_iter(&self) -> std::collections::hash_map::Iter<'_, String, Binding> {
+        self.store.iter()
+    }
+
     pub fn add_outer(&mut self, env: &Rc<RefCell<Enviroment>>) {
         self.outer = Some(Rc::clone(env));
     }
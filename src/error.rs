@@ -0,0 +1,405 @@
+use std::fmt;
+
+use crate::token::{Span, Token};
+
+// エラーに添える補足 (e.g. "function defined here with 2 parameters")
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub span: Span,
+    pub message: String,
+}
+
+// LexError
+#[derive(Clone, Debug, PartialEq)]
+pub enum LexErrorKind {
+    IllegalCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    UnknownEscape(char),
+    // \u{...} の中身が不正 (e.g. "\u{zz}", "\u{d800}")
+    InvalidUnicodeEscape(String),
+    // 0x, 0o, 0b の後に radix 進数として読めない文字がある (e.g. "0b102")
+    InvalidDigit { digit: char, radix: u32 },
+    // 0x, 0o, 0b の後に数字がない
+    MissingDigits { radix: u32 },
+    // 数値の後に `.` が続く, 指数に数字がないなど (e.g. "1.5.2", "1e")
+    MalformedNumber(String),
+    // f64 で表せない大きさの float literal (e.g. "1e400")
+    FloatOutOfRange(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl LexError {
+    pub fn new(kind: LexErrorKind, span: Span) -> Self {
+        LexError { kind, span }
+    }
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexErrorKind::IllegalCharacter(c) => write!(f, "illegal character {:?}", c),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::UnterminatedComment => write!(f, "unterminated block comment"),
+            LexErrorKind::UnknownEscape(c) => write!(f, "unknown escape sequence: \\{}", c),
+            LexErrorKind::InvalidUnicodeEscape(literal) => {
+                write!(f, "invalid unicode escape: \\u{{{}}}", literal)
+            }
+            LexErrorKind::InvalidDigit { digit, radix } => write!(
+                f,
+                "invalid digit {:?} in {} literal",
+                digit,
+                radix_name(*radix)
+            ),
+            LexErrorKind::MissingDigits { radix } => {
+                write!(f, "missing digits in {} literal", radix_name(*radix))
+            }
+            LexErrorKind::MalformedNumber(literal) => {
+                write!(f, "malformed number literal: {}", literal)
+            }
+            LexErrorKind::FloatOutOfRange(literal) => {
+                write!(f, "float literal out of range: {}", literal)
+            }
+        }
+    }
+}
+
+fn radix_name(radix: u32) -> &'static str {
+    match radix {
+        2 => "binary",
+        8 => "octal",
+        16 => "hexadecimal",
+        _ => "integer",
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span.start, self.kind)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+// ParseError
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    // expected は期待していたものの説明 (e.g. "RParen", "expression")
+    UnexpectedToken { expected: String, actual: Token },
+    NestingTooDeep,
+    // loop の外にある break や continue
+    OutsideLoop(&'static str),
+    // 値として使う if の中の break, continue
+    LoopControlInExpression(&'static str),
+    // 代入の左辺が変数や index 式ではない
+    InvalidAssignmentTarget,
+    AssignToConstant(String),
+    RedeclareConstant(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+    pub notes: Vec<Note>,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, span: Span) -> Self {
+        ParseError {
+            kind,
+            span,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_note(mut self, span: Span, message: &str) -> Self {
+        self.notes.push(Note {
+            span,
+            message: message.to_string(),
+        });
+        self
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedToken { expected, actual } => {
+                write!(f, "expected {}, found {:?}", expected, actual)
+            }
+            ParseErrorKind::NestingTooDeep => write!(f, "expression is nested too deeply"),
+            ParseErrorKind::OutsideLoop(keyword) => write!(f, "`{}` outside of a loop", keyword),
+            ParseErrorKind::LoopControlInExpression(keyword) => {
+                write!(f, "`{}` inside an `if` used as a value", keyword)
+            }
+            ParseErrorKind::InvalidAssignmentTarget => write!(f, "invalid assignment target"),
+            ParseErrorKind::AssignToConstant(name) => {
+                write!(f, "cannot assign to constant: {}", name)
+            }
+            ParseErrorKind::RedeclareConstant(name) => {
+                write!(f, "cannot redeclare constant: {}", name)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span.start, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// RuntimeError
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeErrorKind {
+    UnknownIdentifier(String),
+    // let で宣言されていない変数への代入
+    UndeclaredAssignment(String),
+    AssignToConstant(String),
+    RedeclareConstant(String),
+    TypeMismatch {
+        operator: &'static str,
+        left: &'static str,
+        right: &'static str,
+    },
+    // 前置演算子では left は None
+    UnknownOperator {
+        operator: &'static str,
+        left: Option<&'static str>,
+        right: &'static str,
+    },
+    NotCallable(String),
+    InvalidArgument {
+        function: &'static str,
+        expected: &'static str,
+        actual: &'static str,
+    },
+    // builtin が i64 に収まらない整数を受け取った
+    IntegerOutOfRange {
+        function: &'static str,
+        value: String,
+    },
+    ArityMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    DivisionByZero,
+    IndexOutOfBounds {
+        index: String,
+        length: usize,
+    },
+    IndexNotSupported {
+        object: &'static str,
+        index: &'static str,
+    },
+    UnhashableKey(&'static str),
+    NotIterable(&'static str),
+    RecursionLimitExceeded(usize),
+    // 評価の入れ子が深く, 評価に使ってよい stack を使い切った
+    StackExhausted,
+    // evaluator の関数に想定外の node が渡された
+    UnexpectedNode(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub span: Option<Span>,
+    pub notes: Vec<Note>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind) -> Self {
+        RuntimeError {
+            kind,
+            span: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_span(kind: RuntimeErrorKind, span: Span) -> Self {
+        RuntimeError {
+            kind,
+            span: Some(span),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_note(mut self, span: Span, message: &str) -> Self {
+        self.notes.push(Note {
+            span,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    // 位置を持たないエラー (e.g. builtin 関数のエラー) に呼び出し元の位置をつける
+    pub fn or_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = self.span {
+            write!(f, "{}: ", span.start)?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::UnknownIdentifier(name) => {
+                write!(f, "identifier not found: {}", name)
+            }
+            RuntimeErrorKind::TypeMismatch {
+                operator,
+                left,
+                right,
+            } => write!(f, "type mismatch: {} {} {}", left, operator, right),
+            RuntimeErrorKind::UnknownOperator {
+                operator,
+                left: Some(left),
+                right,
+            } => write!(f, "unknown operator: {} {} {}", left, operator, right),
+            RuntimeErrorKind::UnknownOperator {
+                operator,
+                left: None,
+                right,
+            } => write!(f, "unknown operator: {}{}", operator, right),
+            RuntimeErrorKind::UndeclaredAssignment(name) => {
+                write!(f, "cannot assign to undeclared variable: {}", name)
+            }
+            RuntimeErrorKind::AssignToConstant(name) => {
+                write!(f, "cannot assign to constant: {}", name)
+            }
+            RuntimeErrorKind::RedeclareConstant(name) => {
+                write!(f, "cannot redeclare constant: {}", name)
+            }
+            RuntimeErrorKind::NotCallable(literal) => write!(f, "not a function: {}", literal),
+            RuntimeErrorKind::InvalidArgument {
+                function,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "argument to `{}` must be {}, got {}",
+                function, expected, actual
+            ),
+            RuntimeErrorKind::IntegerOutOfRange { function, value } => {
+                write!(f, "integer out of range for `{}`: {}", function, value)
+            }
+            RuntimeErrorKind::ArityMismatch { expected, actual } => write!(
+                f,
+                "wrong number of arguments: expected {}, got {}",
+                expected, actual
+            ),
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::IndexOutOfBounds { index, length } => write!(
+                f,
+                "index out of bounds: the length is {} but the index is {}",
+                length, index
+            ),
+            RuntimeErrorKind::IndexNotSupported { object, index } => {
+                write!(f, "cannot index {} with {}", object, index)
+            }
+            RuntimeErrorKind::UnhashableKey(type_name) => {
+                write!(f, "unusable as hash key: {}", type_name)
+            }
+            RuntimeErrorKind::NotIterable(type_name) => {
+                write!(f, "cannot iterate over {}", type_name)
+            }
+            RuntimeErrorKind::RecursionLimitExceeded(limit) => {
+                write!(f, "maximum recursion depth of {} exceeded", limit)
+            }
+            RuntimeErrorKind::StackExhausted => {
+                write!(f, "evaluation nested too deeply for the available stack")
+            }
+            RuntimeErrorKind::UnexpectedNode(literal) => {
+                write!(f, "unexpected node: {}", literal)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+// lex, parse, eval のいずれかで発生したエラー
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    // script の file を読めなかった
+    Io { path: String, message: String },
+    Lex(LexError),
+    // parser はエラーから回復して読み進めるので, 複数のエラーをまとめて持つ
+    Parse(Vec<ParseError>),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, message } => write!(f, "cannot read {}: {}", path, message),
+            Error::Lex(error) => write!(f, "lex error: {}", error),
+            Error::Parse(errors) => {
+                for (index, error) in errors.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "parse error: {}", error)?;
+                }
+                Ok(())
+            }
+            Error::Runtime(error) => write!(f, "runtime error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { .. } => None,
+            Error::Lex(error) => Some(error),
+            Error::Parse(errors) => errors
+                .first()
+                .map(|error| error as &(dyn std::error::Error + 'static)),
+            Error::Runtime(error) => Some(error),
+        }
+    }
+}
+
+impl From<LexError> for Error {
+    fn from(error: LexError) -> Self {
+        Error::Lex(error)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(vec![error])
+    }
+}
+
+impl From<Vec<ParseError>> for Error {
+    fn from(errors: Vec<ParseError>) -> Self {
+        Error::Parse(errors)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        Error::Runtime(error)
+    }
+}
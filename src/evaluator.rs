@@ -1,22 +1,82 @@
 use crate::ast::*;
+use crate::bigint::BigInt;
+use crate::builtin;
 use crate::env::*;
+use crate::error::{RuntimeError, RuntimeErrorKind};
 use crate::object::*;
-use std::cell::RefCell;
-use std::iter::Zip;
+use crate::token::Span;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::rc::Rc;
 
-pub struct Evaluator {}
+// 関数呼び出しの入れ子の上限の既定値. build の種類によらず同じ値を使う
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+// 文と式の評価は再帰で行うため, Rust の stack を使い切る前に評価を止める.
+// 既定の値は, 既定の 2 MB の thread で評価しても余裕を持って収まる大きさにする
+pub const DEFAULT_STACK_BUDGET: usize = 1024 * 1024;
+
+// 文や式の種類ごとの評価関数. dispatch では関数を選んでから 1 度だけ呼ぶ.
+// arm ごとに呼ぶと debug build では arm の数だけ戻り値の領域が frame に確保され, 再帰 1 段の stack が増える
+type EvalFunction<Node> =
+    fn(&Evaluator, &Node, &mut Rc<RefCell<Enviroment>>) -> Result<Object, RuntimeError>;
+
+pub struct Evaluator {
+    call_depth: Cell<usize>,
+    max_call_depth: usize,
+    // 評価を始めたときの stack の位置. 評価していない間は None
+    stack_base: Cell<Option<usize>>,
+    stack_budget: usize,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Evaluator {
     pub fn new() -> Self {
-        Evaluator {}
+        Evaluator {
+            call_depth: Cell::new(0),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            stack_base: Cell::new(None),
+            stack_budget: DEFAULT_STACK_BUDGET,
+        }
+    }
+
+    // 関数呼び出しをいくつまで入れ子にできるか
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    // 評価に使ってよい stack の大きさ (byte). 評価する thread の stack より小さくする
+    pub fn set_stack_budget(&mut self, stack_budget: usize) {
+        self.stack_budget = stack_budget;
     }
 
     pub fn eavl_program(
         &self,
         program: &Program,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
+    ) -> Result<Object, RuntimeError> {
+        let is_outermost = self.stack_base.get().is_none();
+        if is_outermost {
+            self.stack_base.set(Some(stack_position()));
+        }
+        let result = self.eval_program_statements(program, env);
+        if is_outermost {
+            self.stack_base.set(None);
+        }
+        result
+    }
+
+    fn eval_program_statements(
+        &self,
+        program: &Program,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
         let mut result = Object::Null;
         for statement in program.statement_iter() {
             result = self.eval_statement(statement, env)?;
@@ -33,57 +93,72 @@ impl Evaluator {
         &self,
         statement: &StatementNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
-        let result: Object;
-        result = match statement {
-            StatementNode::ReturnStatement { return_value: _ } => {
-                self.eval_return_statement(statement, env)?
-            }
-            StatementNode::LetStatement {
-                identifier: _,
-                value: _,
-            } => self.eval_let_statement(statement, env)?,
-            StatementNode::ExpressionStatement { expression: _ } => {
-                self.eval_expression_statement(statement, env)?
-            }
-            StatementNode::BlockStatement { statements: _ } => {
-                self.eval_block_statement(statement, env)?
+    ) -> Result<Object, RuntimeError> {
+        if self.stack_exhausted() {
+            return Err(stack_exhausted(statement.span()));
+        }
+        let eval: EvalFunction<StatementNode> = match statement {
+            StatementNode::ReturnStatement { .. } => Self::eval_return_statement,
+            StatementNode::LetStatement { .. } | StatementNode::ConstStatement { .. } => {
+                Self::eval_let_statement
             }
+            StatementNode::ExpressionStatement { .. } => Self::eval_expression_statement,
+            StatementNode::BlockStatement { .. } => Self::eval_block_statement,
+            StatementNode::WhileStatement { .. } => Self::eval_while_statement,
+            StatementNode::ForStatement { .. } => Self::eval_for_statement,
+            StatementNode::BreakStatement { .. } => |_, _, _| Ok(Object::Break),
+            StatementNode::ContinueStatement { .. } => |_, _, _| Ok(Object::Continue),
         };
-
-        Ok(result)
+        eval(self, statement, env)
     }
 
     fn eval_let_statement(
         &self,
         statement: &StatementNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
-        let (identifier, value) = match statement {
-            StatementNode::LetStatement { identifier, value } => (identifier, value),
-            _ => return Err("in eval_let_statement".to_string()),
+    ) -> Result<Object, RuntimeError> {
+        let (identifier, value, mutable) = match statement {
+            StatementNode::LetStatement {
+                identifier, value, ..
+            } => (identifier, value, true),
+            StatementNode::ConstStatement {
+                identifier, value, ..
+            } => (identifier, value, false),
+            _ => return Err(unexpected_statement(statement)),
         };
-        let literal = match identifier.as_ref() {
-            ExpressionNode::Identifier { literal } => literal,
-            _ => return Err("in eval_let_statement".to_string()),
+        let (literal, span) = match identifier.as_ref() {
+            ExpressionNode::Identifier { literal, span } => (literal, span),
+            _ => return Err(unexpected_statement(statement)),
         };
         let object = self.eval_expression(value, env)?;
-        env.as_ref()
-            .borrow_mut()
-            .set(&literal.iter().collect::<String>(), object);
-        Ok(Object::Null)
+
+        // 同じ環境の const は let でも const でも宣言しなおせない. 関数の中などで隠すことはできる
+        let name = literal.iter().collect::<String>();
+        let mut env = env.borrow_mut();
+        let result = if mutable {
+            env.set(&name, object)
+        } else {
+            env.set_const(&name, object)
+        };
+        match result {
+            Ok(()) => Ok(Object::Null),
+            Err(_) => Err(RuntimeError::with_span(
+                RuntimeErrorKind::RedeclareConstant(name),
+                *span,
+            )),
+        }
     }
 
     fn eval_return_statement(
         &self,
         statement: &StatementNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
+    ) -> Result<Object, RuntimeError> {
         let result = match statement {
-            StatementNode::ReturnStatement { return_value } => {
+            StatementNode::ReturnStatement { return_value, .. } => {
                 self.eval_expression(return_value, env)?
             }
-            _ => return Err("in eval_return_statement".to_string()),
+            _ => return Err(unexpected_statement(statement)),
         };
 
         Ok(Object::ReturnValue {
@@ -95,12 +170,12 @@ impl Evaluator {
         &self,
         statement: &StatementNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
+    ) -> Result<Object, RuntimeError> {
         let expression = match statement {
-            StatementNode::ExpressionStatement { expression } => {
+            StatementNode::ExpressionStatement { expression, .. } => {
                 self.eval_expression(expression, env)?
             }
-            _ => return Err("in eval_expression_statement".to_string()),
+            _ => return Err(unexpected_statement(statement)),
         };
 
         Ok(expression)
@@ -110,17 +185,17 @@ impl Evaluator {
         &self,
         statement: &StatementNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
+    ) -> Result<Object, RuntimeError> {
         let statements = match statement {
-            StatementNode::BlockStatement { statements } => statements,
-            _ => return Err("in eval_block_statement".to_string()),
+            StatementNode::BlockStatement { statements, .. } => statements,
+            _ => return Err(unexpected_statement(statement)),
         };
 
         let mut result = Object::Null;
         for statement in statements {
             result = self.eval_statement(statement, env)?;
             result = match result {
-                Object::ReturnValue { value: _ } => return Ok(result),
+                Object::ReturnValue { .. } | Object::Break | Object::Continue => return Ok(result),
                 _ => result,
             }
         }
@@ -128,58 +203,216 @@ impl Evaluator {
         Ok(result)
     }
 
+    fn eval_while_statement(
+        &self,
+        statement: &StatementNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let (condition, body) = match statement {
+            StatementNode::WhileStatement {
+                condition, body, ..
+            } => (condition, body),
+            _ => return Err(unexpected_statement(statement)),
+        };
+
+        loop {
+            let condition_object = self.eval_expression(condition, env)?;
+            if !self.is_truthy(&condition_object) {
+                break;
+            }
+
+            // for と同じく反復ごとに新しい scope で body を評価する. 外側の変数は代入で更新する
+            let mut loop_env = Enviroment::new();
+            loop_env.add_outer(env);
+            match self.eval_statement(body, &mut Rc::new(RefCell::new(loop_env)))? {
+                result @ Object::ReturnValue { .. } => return Ok(result),
+                Object::Break => break,
+                _ => {}
+            }
+        }
+
+        Ok(Object::Null)
+    }
+
+    fn eval_for_statement(
+        &self,
+        statement: &StatementNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let (key, value, iterable, body) = match statement {
+            StatementNode::ForStatement {
+                key,
+                value,
+                iterable,
+                body,
+                ..
+            } => (key, value, iterable, body),
+            _ => return Err(unexpected_statement(statement)),
+        };
+        let key_name = match key.as_deref() {
+            Some(ExpressionNode::Identifier { literal, .. }) => {
+                Some(literal.iter().collect::<String>())
+            }
+            Some(_) => return Err(unexpected_statement(statement)),
+            None => None,
+        };
+        let value_name = match value.as_ref() {
+            ExpressionNode::Identifier { literal, .. } => literal.iter().collect::<String>(),
+            _ => return Err(unexpected_statement(statement)),
+        };
+
+        let iterable_object = self.eval_expression(iterable, env)?;
+        let iter = iterable_object.iter().ok_or_else(|| {
+            RuntimeError::with_span(
+                RuntimeErrorKind::NotIterable(iterable_object.type_name()),
+                iterable.span(),
+            )
+        })?;
+
+        for (key_object, value_object) in iter {
+            // 反復ごとに新しい scope を作り, body で作った closure がその回の値を捕まえるようにする.
+            // 新しい scope には const がないので束縛は失敗しない
+            let mut loop_env = Enviroment::new();
+            loop_env.add_outer(env);
+            if let Some(key_name) = &key_name {
+                let _ = loop_env.set(key_name, key_object);
+            }
+            let _ = loop_env.set(&value_name, value_object);
+
+            match self.eval_statement(body, &mut Rc::new(RefCell::new(loop_env)))? {
+                result @ Object::ReturnValue { .. } => return Ok(result),
+                Object::Break => break,
+                _ => {}
+            }
+        }
+
+        Ok(Object::Null)
+    }
+
     fn eval_expression(
         &self,
         expression: &ExpressionNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
-        let result = match expression {
-            ExpressionNode::Integer { literal: _ } => self.eval_integer(expression)?,
-            ExpressionNode::Boolean { boolean_type: _ } => self.eval_boolean(expression)?,
-            ExpressionNode::Identifier { literal: _ } => self.eval_identifier(expression, env)?,
-            ExpressionNode::PrefixOperator {
-                operator_type: _,
-                right: _,
-            } => self.eval_prefix_operator(expression, env)?,
-            ExpressionNode::InfixOperator {
-                operator_type: _,
-                left: _,
-                right: _,
-            } => self.eval_infix_operator(expression, env)?,
-            ExpressionNode::IfExpression {
-                condition: _,
-                consequence: _,
-                alternative: _,
-            } => self.eval_if_expression(expression, env)?,
-            ExpressionNode::FunctionLiteral {
-                parameters: _,
-                body: _,
-            } => self.eval_function_literal(expression, env)?,
-            ExpressionNode::CallExpression {
-                function: _,
-                arguments: _,
-            } => self.eval_call_expression(expression, env)?,
-            _ => panic!(""),
+    ) -> Result<Object, RuntimeError> {
+        if self.stack_exhausted() {
+            return Err(stack_exhausted(expression.span()));
+        }
+        let eval: EvalFunction<ExpressionNode> = match expression {
+            ExpressionNode::Integer { .. } | ExpressionNode::BigInteger { .. } => {
+                |evaluator, expression, _| evaluator.eval_integer(expression)
+            }
+            ExpressionNode::Float { .. } => {
+                |evaluator, expression, _| evaluator.eval_float(expression)
+            }
+            ExpressionNode::StringLiteral { .. } => {
+                |evaluator, expression, _| evaluator.eval_string(expression)
+            }
+            ExpressionNode::ArrayLiteral { .. } => Self::eval_array_literal,
+            ExpressionNode::HashLiteral { .. } => Self::eval_hash_literal,
+            ExpressionNode::Boolean { .. } => {
+                |evaluator, expression, _| evaluator.eval_boolean(expression)
+            }
+            ExpressionNode::Identifier { .. } => {
+                |evaluator, expression, env| evaluator.eval_identifier(expression, env)
+            }
+            ExpressionNode::PrefixOperator { .. } => Self::eval_prefix_operator,
+            ExpressionNode::InfixOperator { .. } => Self::eval_infix_operator,
+            ExpressionNode::LogicalOperator { .. } => Self::eval_logical_operator,
+            ExpressionNode::Assign { .. } => Self::eval_assign,
+            ExpressionNode::IfExpression { .. } => Self::eval_if_expression,
+            ExpressionNode::FunctionLiteral { .. } => Self::eval_function_literal,
+            ExpressionNode::CallExpression { .. } => Self::eval_call_expression,
+            ExpressionNode::IndexExpression { .. } => Self::eval_index_expression,
         };
+        eval(self, expression, env)
+    }
 
-        Ok(result)
+    // 文と式の評価は互いに再帰するので, どちらも評価の前に stack の残りを確かめる
+    fn stack_exhausted(&self) -> bool {
+        match self.stack_base.get() {
+            Some(base) => base.abs_diff(stack_position()) > self.stack_budget,
+            None => false,
+        }
     }
 
-    fn eval_integer(&self, expression: &ExpressionNode) -> Result<Object, String> {
-        let value = match expression {
-            ExpressionNode::Integer { literal } => {
-                literal.iter().collect::<String>().parse().unwrap()
-            }
-            _ => return Err("in eval_interger".to_string()),
+    fn eval_integer(&self, expression: &ExpressionNode) -> Result<Object, RuntimeError> {
+        match expression {
+            ExpressionNode::Integer { value, .. } => Ok(Object::Integer { value: *value }),
+            ExpressionNode::BigInteger { value, .. } => Ok(Object::BigInteger {
+                value: value.clone(),
+            }),
+            _ => Err(unexpected_expression(expression)),
+        }
+    }
+
+    fn eval_float(&self, expression: &ExpressionNode) -> Result<Object, RuntimeError> {
+        let literal = match expression {
+            ExpressionNode::Float { literal, .. } => literal,
+            _ => return Err(unexpected_expression(expression)),
         };
 
-        Ok(Object::Integer { value })
+        match literal.iter().collect::<String>().parse() {
+            Ok(value) => Ok(Object::Float { value }),
+            Err(_) => Err(unexpected_expression(expression)),
+        }
     }
 
-    fn eval_boolean(&self, expression: &ExpressionNode) -> Result<Object, String> {
+    fn eval_string(&self, expression: &ExpressionNode) -> Result<Object, RuntimeError> {
+        let literal = match expression {
+            ExpressionNode::StringLiteral { literal, .. } => literal,
+            _ => return Err(unexpected_expression(expression)),
+        };
+
+        Ok(Object::String {
+            value: literal.iter().collect::<String>(),
+        })
+    }
+
+    fn eval_array_literal(
+        &self,
+        expression: &ExpressionNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let elements = match expression {
+            ExpressionNode::ArrayLiteral { elements, .. } => elements,
+            _ => return Err(unexpected_expression(expression)),
+        };
+
+        let mut element_objects = Vec::new();
+        for element in elements {
+            element_objects.push(self.eval_expression(element, env)?);
+        }
+
+        Ok(Object::Array {
+            elements: element_objects,
+        })
+    }
+
+    fn eval_hash_literal(
+        &self,
+        expression: &ExpressionNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let pairs = match expression {
+            ExpressionNode::HashLiteral { pairs, .. } => pairs,
+            _ => return Err(unexpected_expression(expression)),
+        };
+
+        let mut hash_pairs = BTreeMap::new();
+        for (key, value) in pairs {
+            let key_object = self.eval_expression(key, env)?;
+            let hash_key = self.to_hash_key(&key_object, key)?;
+            let value_object = self.eval_expression(value, env)?;
+            hash_pairs.insert(hash_key, value_object);
+        }
+
+        Ok(Object::Hash { pairs: hash_pairs })
+    }
+
+    fn eval_boolean(&self, expression: &ExpressionNode) -> Result<Object, RuntimeError> {
         let boolean_type = match expression {
-            ExpressionNode::Boolean { boolean_type } => boolean_type,
-            _ => return Err("in eval_boolean".to_string()),
+            ExpressionNode::Boolean { boolean_type, .. } => boolean_type,
+            _ => return Err(unexpected_expression(expression)),
         };
 
         let value = match boolean_type {
@@ -194,17 +427,22 @@ impl Evaluator {
         &self,
         expression: &ExpressionNode,
         env: &Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
-        let literal = match expression {
-            ExpressionNode::Identifier { literal } => literal,
-            _ => return Err("in eval_identifier".to_string()),
+    ) -> Result<Object, RuntimeError> {
+        let (literal, span) = match expression {
+            ExpressionNode::Identifier { literal, span } => (literal, span),
+            _ => return Err(unexpected_expression(expression)),
         };
 
         let literal_string = literal.iter().collect::<String>();
-        let object = env
-            .borrow()
-            .get(&literal_string)
-            .ok_or(format!("{} is not founded.", &literal_string))?;
+        let object = env.borrow().get(&literal_string);
+        let object = object
+            .or_else(|| builtin::lookup(&literal_string))
+            .ok_or_else(|| {
+                RuntimeError::with_span(
+                    RuntimeErrorKind::UnknownIdentifier(literal_string.clone()),
+                    *span,
+                )
+            })?;
         Ok(object)
     }
 
@@ -212,24 +450,40 @@ impl Evaluator {
         &self,
         expression: &ExpressionNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
-        let (operator_type, right) = match expression {
+    ) -> Result<Object, RuntimeError> {
+        let (operator_type, right, span) = match expression {
             ExpressionNode::PrefixOperator {
                 operator_type,
                 right,
-            } => (operator_type, right),
-            _ => return Err("in eval_boolean".to_string()),
+                span,
+            } => (operator_type, right, span),
+            _ => return Err(unexpected_expression(expression)),
         };
 
         let right_object = self.eval_expression(right, env)?;
+        let right_type = right_object.type_name();
         let result = match (operator_type, right_object) {
-            (PrefixOperatorType::Minus, Object::Integer { value }) => {
-                Object::Integer { value: -1 * value }
+            (PrefixOperatorType::Minus, Object::Integer { value }) => match value.checked_neg() {
+                Some(value) => Object::Integer { value },
+                None => Object::from_big_int(-&BigInt::from_i64(value)),
+            },
+            (PrefixOperatorType::Minus, Object::BigInteger { value }) => {
+                Object::from_big_int(-&value)
             }
+            (PrefixOperatorType::Minus, Object::Float { value }) => Object::Float { value: -value },
             (PrefixOperatorType::Bang, Object::Boolean { value }) => {
                 Object::Boolean { value: !value }
             }
-            (_, _) => Object::Null,
+            (_, _) => {
+                return Err(RuntimeError::with_span(
+                    RuntimeErrorKind::UnknownOperator {
+                        operator: operator_type.literal(),
+                        left: None,
+                        right: right_type,
+                    },
+                    *span,
+                ))
+            }
         };
 
         Ok(result)
@@ -239,108 +493,190 @@ impl Evaluator {
         &self,
         expression: &ExpressionNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
-        let (oprator_type, left, right) = match expression {
+    ) -> Result<Object, RuntimeError> {
+        let (oprator_type, left, right, span) = match expression {
             ExpressionNode::InfixOperator {
                 operator_type,
                 left,
                 right,
-            } => (operator_type, left, right),
-            _ => return Err("in eval_infix_operator".to_string()),
+                span,
+            } => (operator_type, left, right, span),
+            _ => return Err(unexpected_expression(expression)),
         };
 
         let left_object = self.eval_expression(left, env)?;
         let right_object = self.eval_expression(right, env)?;
+        eval_infix_objects(oprator_type, left_object, right_object, *span)
+    }
 
-        let result = match (oprator_type, left_object, right_object) {
-            (
-                InfixOperatorType::Plus,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Integer {
-                value: left_value + right_value,
-            },
-            (
-                InfixOperatorType::Minus,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Integer {
-                value: left_value - right_value,
-            },
-            (
-                InfixOperatorType::Asterisk,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Integer {
-                value: left_value * right_value,
-            },
-            (
-                InfixOperatorType::Slash,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Integer {
-                value: left_value / right_value,
-            },
-            (
-                InfixOperatorType::Lt,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Boolean {
-                value: left_value < right_value,
-            },
-            (
-                InfixOperatorType::Gt,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Boolean {
-                value: left_value > right_value,
-            },
-            (
-                InfixOperatorType::Eq,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Boolean {
-                value: left_value == right_value,
-            },
-            (
-                InfixOperatorType::NotEq,
-                Object::Integer { value: left_value },
-                Object::Integer { value: right_value },
-            ) => Object::Boolean {
-                value: left_value != right_value,
-            },
-            (
-                InfixOperatorType::Eq,
-                Object::Boolean { value: left_value },
-                Object::Boolean { value: right_value },
-            ) => Object::Boolean {
-                value: left_value == right_value,
-            },
-            (
-                InfixOperatorType::NotEq,
-                Object::Boolean { value: left_value },
-                Object::Boolean { value: right_value },
-            ) => Object::Boolean {
-                value: left_value != right_value,
-            },
-            (_, _, _) => Object::Null,
+    // 左辺で結果が決まれば右辺は評価しない. 結果は is_truthy による boolean
+    fn eval_logical_operator(
+        &self,
+        expression: &ExpressionNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let (operator_type, left, right) = match expression {
+            ExpressionNode::LogicalOperator {
+                operator_type,
+                left,
+                right,
+                ..
+            } => (operator_type, left, right),
+            _ => return Err(unexpected_expression(expression)),
         };
 
-        Ok(result)
+        let left_object = self.eval_expression(left, env)?;
+        let left_value = self.is_truthy(&left_object);
+        let value = match (operator_type, left_value) {
+            (LogicalOperatorType::And, false) | (LogicalOperatorType::Or, true) => left_value,
+            (_, _) => {
+                let right_object = self.eval_expression(right, env)?;
+                self.is_truthy(&right_object)
+            }
+        };
+
+        Ok(Object::Boolean { value })
+    }
+
+    // 代入した値を返す
+    fn eval_assign(
+        &self,
+        expression: &ExpressionNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let (operator_type, target, value, span) = match expression {
+            ExpressionNode::Assign {
+                operator_type,
+                target,
+                value,
+                span,
+            } => (operator_type, target, value, span),
+            _ => return Err(unexpected_expression(expression)),
+        };
+
+        let value_object = self.eval_expression(value, env)?;
+        let infix_operator = operator_type.infix_operator();
+        let mut assigned = Object::Null;
+        self.assign_to(target, env, &mut |old| {
+            let new = match &infix_operator {
+                Some(operator) => eval_infix_objects(operator, old, value_object.clone(), *span)?,
+                None => value_object.clone(),
+            };
+            assigned = new.clone();
+            Ok(new)
+        })?;
+
+        Ok(assigned)
+    }
+
+    // target の今の値を update に渡し, 返った値で置き換える
+    // index 式なら要素を置き換えた配列や hash を作り, それで外側の target を置き換える
+    fn assign_to(
+        &self,
+        target: &ExpressionNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+        update: &mut dyn FnMut(Object) -> Result<Object, RuntimeError>,
+    ) -> Result<(), RuntimeError> {
+        match target {
+            ExpressionNode::Identifier { literal, span } => {
+                let name = literal.iter().collect::<String>();
+                let undeclared = || {
+                    RuntimeError::with_span(
+                        RuntimeErrorKind::UndeclaredAssignment(name.clone()),
+                        *span,
+                    )
+                };
+                let constant = || {
+                    RuntimeError::with_span(RuntimeErrorKind::AssignToConstant(name.clone()), *span)
+                };
+                match env.borrow().is_mutable(&name) {
+                    Some(true) => {}
+                    Some(false) => return Err(constant()),
+                    None => return Err(undeclared()),
+                }
+
+                let old = env.borrow().get(&name).ok_or_else(undeclared)?;
+                let new = update(old)?;
+                env.borrow_mut()
+                    .assign(&name, new)
+                    .map_err(|error| match error {
+                        AssignError::Undeclared => undeclared(),
+                        AssignError::Constant => constant(),
+                    })
+            }
+            ExpressionNode::IndexExpression { left, index, span } => {
+                let index_object = self.eval_expression(index, env)?;
+                self.assign_to(left, env, &mut |container| {
+                    self.update_index(container, &index_object, index, *span, update)
+                })
+            }
+            _ => Err(unexpected_expression(target)),
+        }
+    }
+
+    fn update_index(
+        &self,
+        container: Object,
+        index_object: &Object,
+        index: &ExpressionNode,
+        span: Span,
+        update: &mut dyn FnMut(Object) -> Result<Object, RuntimeError>,
+    ) -> Result<Object, RuntimeError> {
+        match (container, index_object) {
+            (
+                Object::Array { mut elements },
+                Object::Integer { .. } | Object::BigInteger { .. },
+            ) => {
+                // 範囲外の index に代入して配列を伸ばすことはできない
+                let position = match index_object {
+                    Object::Integer { value } => {
+                        usize::try_from(*value).ok().filter(|&i| i < elements.len())
+                    }
+                    _ => None,
+                };
+                let i = position.ok_or_else(|| {
+                    RuntimeError::with_span(
+                        RuntimeErrorKind::IndexOutOfBounds {
+                            index: index_object.literal(),
+                            length: elements.len(),
+                        },
+                        index.span(),
+                    )
+                })?;
+                let old = std::mem::replace(&mut elements[i], Object::Null);
+                elements[i] = update(old)?;
+                Ok(Object::Array { elements })
+            }
+            (Object::Hash { mut pairs }, _) => {
+                // 存在しない key には新しく追加する
+                let hash_key = self.to_hash_key(index_object, index)?;
+                let old = pairs.remove(&hash_key).unwrap_or(Object::Null);
+                pairs.insert(hash_key, update(old)?);
+                Ok(Object::Hash { pairs })
+            }
+            (container, _) => Err(RuntimeError::with_span(
+                RuntimeErrorKind::IndexNotSupported {
+                    object: container.type_name(),
+                    index: index_object.type_name(),
+                },
+                span,
+            )),
+        }
     }
 
     fn eval_if_expression(
         &self,
         expression: &ExpressionNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
+    ) -> Result<Object, RuntimeError> {
         let (condition, consequence, alternative) = match expression {
             ExpressionNode::IfExpression {
                 condition,
                 consequence,
                 alternative,
+                ..
             } => (condition, consequence, alternative),
-            _ => return Err("in eval_if_expression".to_string()),
+            _ => return Err(unexpected_expression(expression)),
         };
 
         let condition_object = self.eval_expression(condition, env)?;
@@ -357,18 +693,26 @@ impl Evaluator {
         Ok(result)
     }
 
-    fn eval_function_literal(&self, expression: &ExpressionNode, env: &mut Rc<RefCell<Enviroment>>) -> Result<Object, String> {
-        let (parameters, body) = match expression {
-            ExpressionNode::FunctionLiteral { parameters, body } => (parameters, body),
-            _ => return Err(format!("on eval_function_literal. {}", expression.literal())),
+    fn eval_function_literal(
+        &self,
+        expression: &ExpressionNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let (parameters, body, span) = match expression {
+            ExpressionNode::FunctionLiteral {
+                parameters,
+                body,
+                span,
+            } => (parameters, body, span),
+            _ => return Err(unexpected_expression(expression)),
         };
 
         let mut parameter_strings = Vec::<String>::new();
         for parameter in parameters {
-            if let ExpressionNode::Identifier { literal } = parameter.as_ref() {
+            if let ExpressionNode::Identifier { literal, .. } = parameter.as_ref() {
                 parameter_strings.push(literal.iter().collect::<String>());
             } else {
-                return Err("in eval_function_literal".to_string());
+                return Err(unexpected_expression(expression));
             }
         }
 
@@ -376,6 +720,7 @@ impl Evaluator {
             parameters: parameter_strings,
             body: Box::new(body.as_ref().clone()),
             env: Some(Rc::clone(env)),
+            span: *span,
         })
     }
 
@@ -383,42 +728,198 @@ impl Evaluator {
         &self,
         expression: &ExpressionNode,
         env: &mut Rc<RefCell<Enviroment>>,
-    ) -> Result<Object, String> {
-        let (function, arguments) = match expression {
+    ) -> Result<Object, RuntimeError> {
+        let (function, arguments, span) = match expression {
             ExpressionNode::CallExpression {
                 function,
                 arguments,
-            } => (function, arguments),
-            _ => return Err("in eval_call_expression".to_string()),
+                span,
+            } => (function, arguments, span),
+            _ => return Err(unexpected_expression(expression)),
         };
 
-        let function = self.eval_expression(function, env)?;
-        let (parameters, body, func_env) = match function {
+        let function_object = self.eval_expression(function, env)?;
+        match function_object {
+            Object::FunctionObject { .. } => {
+                self.apply_function(function_object, arguments, *span, env)
+            }
+            _ => self.apply_builtin(function_object, function.span(), arguments, *span, env),
+        }
+    }
+
+    // builtin と host の関数を呼ぶ. 関数でなければエラー
+    fn apply_builtin(
+        &self,
+        function: Object,
+        function_span: Span,
+        arguments: &[Box<ExpressionNode>],
+        span: Span,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        match function {
+            Object::Builtin { function, .. } => {
+                let argument_objects = self.eval_arguments(arguments, env)?;
+                function(&argument_objects).map_err(|error| error.or_span(span))
+            }
+            Object::HostFunction {
+                arity, function, ..
+            } => {
+                if arity != arguments.len() {
+                    return Err(RuntimeError::with_span(
+                        RuntimeErrorKind::ArityMismatch {
+                            expected: arity,
+                            actual: arguments.len(),
+                        },
+                        span,
+                    ));
+                }
+                let argument_objects = self.eval_arguments(arguments, env)?;
+                function(&argument_objects).map_err(|error| error.or_span(span))
+            }
+            _ => Err(RuntimeError::with_span(
+                RuntimeErrorKind::NotCallable(function.literal()),
+                function_span,
+            )),
+        }
+    }
+
+    // Monkey で定義した関数を呼ぶ. 再帰のたびに stack に積まれるので, エラーの組み立ては別の関数で行う
+    fn apply_function(
+        &self,
+        function: Object,
+        arguments: &[Box<ExpressionNode>],
+        span: Span,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let (parameters, body, func_env, func_span) = match function {
             Object::FunctionObject {
                 parameters,
                 body,
                 env: func_env,
-            } => (parameters, body, func_env.unwrap()),
-            _ => return Err("".to_string()),
+                span: func_span,
+            } => (parameters, body, func_env, func_span),
+            _ => {
+                return Err(RuntimeError::with_span(
+                    RuntimeErrorKind::NotCallable(function.literal()),
+                    span,
+                ))
+            }
         };
 
+        if parameters.len() != arguments.len() {
+            return Err(arity_mismatch(
+                parameters.len(),
+                arguments.len(),
+                span,
+                func_span,
+            ));
+        }
+
         let mut new_env = Enviroment::new();
-        new_env.add_outer(&func_env);
+        if let Some(func_env) = &func_env {
+            new_env.add_outer(func_env);
+        }
 
         for (parameter, argument) in parameters.iter().zip(arguments.iter()) {
             let evaled_object = self.eval_expression(argument, env)?;
-            new_env.set(parameter, evaled_object);
+            // 新しい環境には const がないので束縛は失敗しない
+            let _ = new_env.set(parameter, evaled_object);
         }
 
-        let func_evaled_object =
-            self.eval_block_statement(body.as_ref(), &mut Rc::new(RefCell::new(new_env)))?;
+        // 上限は呼び出しの数で数える. 1 回の呼び出しで使う stack は body によって変わる
+        if self.call_depth.get() >= self.max_call_depth {
+            return Err(recursion_limit_exceeded(self.max_call_depth, span));
+        }
+        self.call_depth.set(self.call_depth.get() + 1);
+        let result = self.eval_statement(body.as_ref(), &mut Rc::new(RefCell::new(new_env)));
+        self.call_depth.set(self.call_depth.get() - 1);
 
-        match func_evaled_object {
-            Object::ReturnValue { value } => Ok(value.as_ref().to_owned()),
-            _ => Ok(func_evaled_object),
+        match result? {
+            Object::ReturnValue { value } => Ok(*value),
+            object => Ok(object),
         }
     }
 
+    fn eval_index_expression(
+        &self,
+        expression: &ExpressionNode,
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Object, RuntimeError> {
+        let (left, index, span) = match expression {
+            ExpressionNode::IndexExpression { left, index, span } => (left, index, span),
+            _ => return Err(unexpected_expression(expression)),
+        };
+
+        let left_object = self.eval_expression(left, env)?;
+        let index_object = self.eval_expression(index, env)?;
+
+        match (&left_object, &index_object) {
+            (Object::Array { elements }, Object::Integer { value }) => {
+                // 範囲外の index は null ではなくエラーにする
+                usize::try_from(*value)
+                    .ok()
+                    .and_then(|i| elements.get(i))
+                    .cloned()
+                    .ok_or_else(|| {
+                        RuntimeError::with_span(
+                            RuntimeErrorKind::IndexOutOfBounds {
+                                index: value.to_string(),
+                                length: elements.len(),
+                            },
+                            index.span(),
+                        )
+                    })
+            }
+            (Object::Array { elements }, Object::BigInteger { value }) => {
+                Err(RuntimeError::with_span(
+                    RuntimeErrorKind::IndexOutOfBounds {
+                        index: value.to_string(),
+                        length: elements.len(),
+                    },
+                    index.span(),
+                ))
+            }
+            (Object::Hash { pairs }, _) => {
+                // 存在しない key は null
+                let hash_key = self.to_hash_key(&index_object, index)?;
+                Ok(pairs.get(&hash_key).cloned().unwrap_or(Object::Null))
+            }
+            _ => Err(RuntimeError::with_span(
+                RuntimeErrorKind::IndexNotSupported {
+                    object: left_object.type_name(),
+                    index: index_object.type_name(),
+                },
+                *span,
+            )),
+        }
+    }
+
+    fn eval_arguments(
+        &self,
+        arguments: &[Box<ExpressionNode>],
+        env: &mut Rc<RefCell<Enviroment>>,
+    ) -> Result<Vec<Object>, RuntimeError> {
+        let mut argument_objects = Vec::new();
+        for argument in arguments {
+            argument_objects.push(self.eval_expression(argument, env)?);
+        }
+        Ok(argument_objects)
+    }
+
+    // key は hash の key に使える値でなければならない
+    fn to_hash_key(
+        &self,
+        object: &Object,
+        expression: &ExpressionNode,
+    ) -> Result<HashKey, RuntimeError> {
+        HashKey::from_object(object).ok_or_else(|| {
+            RuntimeError::with_span(
+                RuntimeErrorKind::UnhashableKey(object.type_name()),
+                expression.span(),
+            )
+        })
+    }
+
     fn is_truthy(&self, object: &Object) -> bool {
         match object {
             Object::Null => false,
@@ -429,6 +930,308 @@ impl Evaluator {
     }
 }
 
+// 評価済みの 2 つの値に二項演算子を適用する
+fn eval_infix_objects(
+    operator_type: &InfixOperatorType,
+    left_object: Object,
+    right_object: Object,
+    span: Span,
+) -> Result<Object, RuntimeError> {
+    let (left_type, right_type) = (left_object.type_name(), right_object.type_name());
+
+    let result = match (operator_type, left_object, right_object) {
+        (
+            operator_type,
+            left_object @ (Object::Integer { .. } | Object::BigInteger { .. }),
+            right_object @ (Object::Integer { .. } | Object::BigInteger { .. }),
+        ) => eval_integer_infix(operator_type, &left_object, &right_object, span)?,
+        // どちらかが float なら両方を f64 にして計算する
+        (
+            operator_type,
+            left_object @ (Object::Integer { .. }
+            | Object::BigInteger { .. }
+            | Object::Float { .. }),
+            right_object @ (Object::Integer { .. }
+            | Object::BigInteger { .. }
+            | Object::Float { .. }),
+        ) => eval_float_infix(operator_type, to_f64(&left_object), to_f64(&right_object)),
+        (
+            InfixOperatorType::Eq,
+            Object::Boolean { value: left_value },
+            Object::Boolean { value: right_value },
+        ) => Object::Boolean {
+            value: left_value == right_value,
+        },
+        (
+            InfixOperatorType::NotEq,
+            Object::Boolean { value: left_value },
+            Object::Boolean { value: right_value },
+        ) => Object::Boolean {
+            value: left_value != right_value,
+        },
+        (
+            InfixOperatorType::Plus,
+            Object::String { value: left_value },
+            Object::String { value: right_value },
+        ) => Object::String {
+            value: left_value + &right_value,
+        },
+        (
+            InfixOperatorType::Eq,
+            Object::String { value: left_value },
+            Object::String { value: right_value },
+        ) => Object::Boolean {
+            value: left_value == right_value,
+        },
+        (
+            InfixOperatorType::NotEq,
+            Object::String { value: left_value },
+            Object::String { value: right_value },
+        ) => Object::Boolean {
+            value: left_value != right_value,
+        },
+        (_, _, _) => {
+            // 型が違えば TypeMismatch, 同じ型でも対応していない演算子なら UnknownOperator
+            let operator = operator_type.literal();
+            let kind = if left_type != right_type {
+                RuntimeErrorKind::TypeMismatch {
+                    operator,
+                    left: left_type,
+                    right: right_type,
+                }
+            } else {
+                RuntimeErrorKind::UnknownOperator {
+                    operator,
+                    left: Some(left_type),
+                    right: right_type,
+                }
+            };
+            return Err(RuntimeError::with_span(kind, span));
+        }
+    };
+
+    Ok(result)
+}
+
+// 整数の四則演算, 剰余と比較. i64 で overflow したら BigInt で計算しなおす
+fn eval_integer_infix(
+    operator_type: &InfixOperatorType,
+    left: &Object,
+    right: &Object,
+    span: Span,
+) -> Result<Object, RuntimeError> {
+    if let (Object::Integer { value: left_value }, Object::Integer { value: right_value }) =
+        (left, right)
+    {
+        let (left_value, right_value) = (*left_value, *right_value);
+        let value = match operator_type {
+            InfixOperatorType::Plus => left_value.checked_add(right_value),
+            InfixOperatorType::Minus => left_value.checked_sub(right_value),
+            InfixOperatorType::Asterisk => left_value.checked_mul(right_value),
+            InfixOperatorType::Slash => {
+                if right_value == 0 {
+                    return Err(RuntimeError::with_span(
+                        RuntimeErrorKind::DivisionByZero,
+                        span,
+                    ));
+                }
+                left_value.checked_div(right_value)
+            }
+            InfixOperatorType::Percent => {
+                if right_value == 0 {
+                    return Err(RuntimeError::with_span(
+                        RuntimeErrorKind::DivisionByZero,
+                        span,
+                    ));
+                }
+                left_value.checked_rem(right_value)
+            }
+            InfixOperatorType::Lt => {
+                return Ok(Object::Boolean {
+                    value: left_value < right_value,
+                })
+            }
+            InfixOperatorType::Gt => {
+                return Ok(Object::Boolean {
+                    value: left_value > right_value,
+                })
+            }
+            InfixOperatorType::LtEq => {
+                return Ok(Object::Boolean {
+                    value: left_value <= right_value,
+                })
+            }
+            InfixOperatorType::GtEq => {
+                return Ok(Object::Boolean {
+                    value: left_value >= right_value,
+                })
+            }
+            InfixOperatorType::Eq => {
+                return Ok(Object::Boolean {
+                    value: left_value == right_value,
+                })
+            }
+            InfixOperatorType::NotEq => {
+                return Ok(Object::Boolean {
+                    value: left_value != right_value,
+                })
+            }
+        };
+        if let Some(value) = value {
+            return Ok(Object::Integer { value });
+        }
+    }
+
+    let (left_value, right_value) = (to_big_int(left), to_big_int(right));
+    let value = match operator_type {
+        InfixOperatorType::Plus => &left_value + &right_value,
+        InfixOperatorType::Minus => &left_value - &right_value,
+        InfixOperatorType::Asterisk => &left_value * &right_value,
+        InfixOperatorType::Slash => left_value
+            .checked_div(&right_value)
+            .ok_or_else(|| RuntimeError::with_span(RuntimeErrorKind::DivisionByZero, span))?,
+        InfixOperatorType::Percent => left_value
+            .checked_rem(&right_value)
+            .ok_or_else(|| RuntimeError::with_span(RuntimeErrorKind::DivisionByZero, span))?,
+        InfixOperatorType::Lt => {
+            return Ok(Object::Boolean {
+                value: left_value < right_value,
+            })
+        }
+        InfixOperatorType::Gt => {
+            return Ok(Object::Boolean {
+                value: left_value > right_value,
+            })
+        }
+        InfixOperatorType::LtEq => {
+            return Ok(Object::Boolean {
+                value: left_value <= right_value,
+            })
+        }
+        InfixOperatorType::GtEq => {
+            return Ok(Object::Boolean {
+                value: left_value >= right_value,
+            })
+        }
+        InfixOperatorType::Eq => {
+            return Ok(Object::Boolean {
+                value: left_value == right_value,
+            })
+        }
+        InfixOperatorType::NotEq => {
+            return Ok(Object::Boolean {
+                value: left_value != right_value,
+            })
+        }
+    };
+    Ok(Object::from_big_int(value))
+}
+
+// 浮動小数点数の四則演算, 剰余と比較. 0 での除算は IEEE 754 に従い inf か NaN になる
+fn eval_float_infix(
+    operator_type: &InfixOperatorType,
+    left_value: f64,
+    right_value: f64,
+) -> Object {
+    match operator_type {
+        InfixOperatorType::Plus => Object::Float {
+            value: left_value + right_value,
+        },
+        InfixOperatorType::Minus => Object::Float {
+            value: left_value - right_value,
+        },
+        InfixOperatorType::Asterisk => Object::Float {
+            value: left_value * right_value,
+        },
+        InfixOperatorType::Slash => Object::Float {
+            value: left_value / right_value,
+        },
+        InfixOperatorType::Percent => Object::Float {
+            value: left_value % right_value,
+        },
+        InfixOperatorType::Lt => Object::Boolean {
+            value: left_value < right_value,
+        },
+        InfixOperatorType::Gt => Object::Boolean {
+            value: left_value > right_value,
+        },
+        InfixOperatorType::LtEq => Object::Boolean {
+            value: left_value <= right_value,
+        },
+        InfixOperatorType::GtEq => Object::Boolean {
+            value: left_value >= right_value,
+        },
+        InfixOperatorType::Eq => Object::Boolean {
+            value: left_value == right_value,
+        },
+        InfixOperatorType::NotEq => Object::Boolean {
+            value: left_value != right_value,
+        },
+    }
+}
+
+fn to_f64(object: &Object) -> f64 {
+    match object {
+        Object::Float { value } => *value,
+        Object::Integer { value } => *value as f64,
+        Object::BigInteger { value } => value.to_f64(),
+        _ => f64::NAN,
+    }
+}
+
+fn to_big_int(object: &Object) -> BigInt {
+    match object {
+        Object::BigInteger { value } => value.clone(),
+        Object::Integer { value } => BigInt::from_i64(*value),
+        _ => BigInt::zero(),
+    }
+}
+
+// 呼び出しの引数の数が関数の引数の数と合わない
+fn arity_mismatch(expected: usize, actual: usize, span: Span, func_span: Span) -> RuntimeError {
+    RuntimeError::with_span(RuntimeErrorKind::ArityMismatch { expected, actual }, span).with_note(
+        func_span,
+        &format!(
+            "function defined here with {} parameter{}",
+            expected,
+            if expected == 1 { "" } else { "s" }
+        ),
+    )
+}
+
+fn recursion_limit_exceeded(max_call_depth: usize, span: Span) -> RuntimeError {
+    RuntimeError::with_span(
+        RuntimeErrorKind::RecursionLimitExceeded(max_call_depth),
+        span,
+    )
+}
+
+fn stack_exhausted(span: Span) -> RuntimeError {
+    RuntimeError::with_span(RuntimeErrorKind::StackExhausted, span)
+}
+
+// 今の stack の位置. 局所変数の address で近似する
+#[inline(never)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::ptr::addr_of!(marker) as usize
+}
+
+// dispatch を経由していれば到達しない
+fn unexpected_statement(statement: &StatementNode) -> RuntimeError {
+    RuntimeError::with_span(
+        RuntimeErrorKind::UnexpectedNode(statement.literal()),
+        statement.span(),
+    )
+}
+
+fn unexpected_expression(expression: &ExpressionNode) -> RuntimeError {
+    RuntimeError::with_span(
+        RuntimeErrorKind::UnexpectedNode(expression.literal()),
+        expression.span(),
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -436,7 +1239,7 @@ mod test {
     fn test_eval(expect_strings: Vec<&str>, test_strings: Vec<&str>) {
         let evaluator = Evaluator::new();
         for (&test_string, &expect_sting) in test_strings.iter().zip(expect_strings.iter()) {
-            let program = crate::parser::parse(crate::lexer::lex(test_string)).unwrap();
+            let program = crate::parser::parse(crate::lexer::lex(test_string).unwrap()).unwrap();
             let mut global_env = Rc::new(RefCell::new(Enviroment::new()));
             let actual_string = evaluator
                 .eavl_program(&program, &mut global_env)
@@ -446,10 +1249,44 @@ mod test {
         }
     }
 
+    fn test_eval_error(expect_strings: Vec<&str>, test_strings: Vec<&str>) {
+        let evaluator = Evaluator::new();
+        for (&test_string, &expect_sting) in test_strings.iter().zip(expect_strings.iter()) {
+            let program = crate::parser::parse(crate::lexer::lex(test_string).unwrap()).unwrap();
+            let mut global_env = Rc::new(RefCell::new(Enviroment::new()));
+            let actual_string = evaluator
+                .eavl_program(&program, &mut global_env)
+                .err()
+                .unwrap()
+                .to_string();
+            assert_eq!(expect_sting, actual_string);
+        }
+    }
+
+    fn test_eval_error_kind(expect_kinds: Vec<RuntimeErrorKind>, test_strings: Vec<&str>) {
+        let evaluator = Evaluator::new();
+        for (&test_string, expect_kind) in test_strings.iter().zip(expect_kinds.iter()) {
+            let program = crate::parser::parse(crate::lexer::lex(test_string).unwrap()).unwrap();
+            let mut global_env = Rc::new(RefCell::new(Enviroment::new()));
+            let error = evaluator
+                .eavl_program(&program, &mut global_env)
+                .err()
+                .unwrap();
+            assert_eq!(expect_kind, &error.kind);
+        }
+    }
+
     #[test]
     fn test_eval_integer_expressions() {
-        let test_strings = vec!["5", "10"];
-        let expect_strings = vec!["5", "10"];
+        let test_strings = vec![
+            "5",
+            "10",
+            "0xFF",
+            "0o755 - 0o700",
+            "0b1010 * 2",
+            "1_000_000",
+        ];
+        let expect_strings = vec!["5", "10", "255", "45", "20", "1000000"];
 
         test_eval(expect_strings, test_strings);
     }
@@ -462,6 +1299,253 @@ mod test {
         test_eval(expect_strings, test_strings);
     }
 
+    #[test]
+    fn test_eval_comparison_and_remainder() {
+        let test_strings = vec![
+            "1 <= 2",
+            "2 <= 2",
+            "3 <= 2",
+            "1 >= 2",
+            "2 >= 2",
+            "let x = 10; x <= 10 == !(x > 10)",
+            "99999999999999999999 >= 99999999999999999999",
+            "1.5 <= 1",
+            "2 >= 1.5",
+            "7 % 3",
+            "-7 % 3",
+            "7 % -3",
+            "1 + 10 % 4 * 2",
+            "-9223372036854775807 - 1 % -1",
+            "(-9223372036854775807 - 1) % -1",
+            "99999999999999999999 % 7",
+            "7.5 % 2",
+        ];
+        let expect_strings = vec![
+            "true",
+            "true",
+            "false",
+            "false",
+            "true",
+            "true",
+            "true",
+            "false",
+            "true",
+            "1",
+            "-1",
+            "1",
+            "5",
+            "-9223372036854775807",
+            "0",
+            "1",
+            "1.5",
+        ];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_logical_operators() {
+        let test_strings = vec![
+            "true && true",
+            "true && false",
+            "false || true",
+            "false || false",
+            "1 && \"a\"",
+            "(if (false) { 1 }) || false",
+            "0 || false",
+            "1 < 2 && 2 < 3",
+            "1 > 2 || 2 > 3 && true",
+            // 左辺で結果が決まれば右辺は評価しない
+            "false && undefined",
+            "true || 1 / 0",
+            "let calls = fn(x) { x }; false && calls(undefined)",
+        ];
+        let expect_strings = vec![
+            "true", "false", "true", "false", "true", "false", "true", "true", "false", "false",
+            "true", "false",
+        ];
+
+        test_eval(expect_strings, test_strings);
+
+        test_eval_error(
+            vec![
+                "1:9: identifier not found: undefined",
+                "1:10: division by zero",
+            ],
+            vec!["true && undefined", "false || 1 / 0"],
+        );
+    }
+
+    #[test]
+    fn test_eval_string_expressions() {
+        let test_strings = vec![
+            r#""hello world""#,
+            r#""hello" + " " + "world""#,
+            r#"let greet = fn(name) { "hi, " + name }; greet("monkey")"#,
+            r#""a\tb""#,
+            r#""monkey" == "monkey""#,
+            r#""monkey" == "gorilla""#,
+            r#""monkey" != "gorilla""#,
+        ];
+        let expect_strings = vec![
+            "hello world",
+            "hello world",
+            "hi, monkey",
+            "a\tb",
+            "true",
+            "false",
+            "true",
+        ];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_array_expressions() {
+        let test_strings = vec![
+            "[1, 2 * 2, 3 + 3]",
+            "[]",
+            r#"["a", 1, [true]]"#,
+            "[1, 2, 3][0]",
+            "[1, 2, 3][1 + 1]",
+            "let i = 0; [1][i]",
+            "let arr = [1, 2, 3]; arr[0] + arr[1] + arr[2]",
+            "let f = fn() { [fn(x) { x * 2 }] }; f()[0](21)",
+        ];
+        let expect_strings = vec![
+            "[1, 4, 6]",
+            "[]",
+            r#"["a", 1, [true]]"#,
+            "1",
+            "3",
+            "1",
+            "6",
+            "42",
+        ];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_hash_expressions() {
+        let test_strings = vec![
+            r#"let two = "two"; {"one": 10 - 9, two: 1 + 1, "thr" + "ee": 6 / 2, 4: 4, true: 5}"#,
+            "{}",
+            r#"{"foo": 5}["foo"]"#,
+            r#"{"foo": 5}["bar"]"#,
+            r#"let key = "foo"; {"foo": 5}[key]"#,
+            "{5: 5}[5]",
+            "{true: 5}[true]",
+            r#"{"a": 1, "a": 2}["a"]"#,
+            r#"{"list": [1, 2], "inner": {"x": "y"}}"#,
+        ];
+        let expect_strings = vec![
+            r#"{4: 4, true: 5, "one": 1, "three": 3, "two": 2}"#,
+            "{}",
+            "5",
+            "null",
+            "5",
+            "5",
+            "5",
+            "2",
+            r#"{"inner": {"x": "y"}, "list": [1, 2]}"#,
+        ];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_builtin_functions() {
+        let test_strings = vec![
+            r#"len("")"#,
+            r#"len("four")"#,
+            r#"len("あいう")"#,
+            "len([1, 2, 3])",
+            r#"len({"a": 1})"#,
+            "first([1, 2, 3])",
+            "first([])",
+            "last([1, 2, 3])",
+            "rest([1, 2, 3])",
+            "rest([])",
+            "let a = [1]; let b = push(a, 2); [a, b]",
+            r#"puts("hello", 1)"#,
+            "type(1)",
+            r#"type({})"#,
+            "type(len)",
+            r#"str([1, "a"]) + "!""#,
+            "len",
+            "let len = fn(x) { 42 }; len([1])",
+            "let map = fn(arr, f) { if (len(arr) == 0) { [] } else { push(map(rest(arr), f), f(first(arr))) } }; map([1, 2, 3], fn(x) { x * 2 })",
+            "range(3)",
+            "range(-1, 2)",
+            "len(range(2, 5))",
+            "len(range(5, 2))",
+            "type(range(1))",
+            "len(range(-9223372036854775807 - 1, 9223372036854775807))",
+            "len(range(-1, 9223372036854775807))",
+        ];
+        let expect_strings = vec![
+            "0",
+            "4",
+            "3",
+            "3",
+            "1",
+            "1",
+            "null",
+            "3",
+            "[2, 3]",
+            "null",
+            "[[1], [1, 2]]",
+            "null",
+            "integer",
+            "hash",
+            "builtin",
+            r#"[1, "a"]!"#,
+            "builtin function len",
+            "42",
+            "[6, 4, 2]",
+            "range(0, 3)",
+            "range(-1, 2)",
+            "3",
+            "0",
+            "range",
+            "18446744073709551615",
+            "9223372036854775808",
+        ];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_builtin_errors() {
+        let test_strings = vec![
+            "len(1)",
+            r#"len("one", "two")"#,
+            "first(1)",
+            "push([])",
+            "let f = fn() { rest(true) };\nf()",
+            "range()",
+            "range(1, 2, 3)",
+            r#"range("a")"#,
+            "range(99999999999999999999)",
+            "range(0, 9223372036854775807 + 1)",
+        ];
+        let expect_strings = vec![
+            "1:1: argument to `len` must be string, array, hash or range, got integer",
+            "1:1: wrong number of arguments: expected 1, got 2",
+            "1:1: argument to `first` must be array, got integer",
+            "1:1: wrong number of arguments: expected 2, got 1",
+            "1:16: argument to `rest` must be array, got boolean",
+            "1:1: wrong number of arguments: expected 1, got 0",
+            "1:1: wrong number of arguments: expected 2, got 3",
+            "1:1: argument to `range` must be integer, got string",
+            "1:1: integer out of range for `range`: 99999999999999999999",
+            "1:1: integer out of range for `range`: 9223372036854775808",
+        ];
+
+        test_eval_error(expect_strings, test_strings);
+    }
+
     #[test]
     fn test_eval_prefix_operator() {
         let test_strings = vec!["!true", "!false", "-10", "--5"];
@@ -541,6 +1625,237 @@ mod test {
         test_eval(expect_strings, test_strings);
     }
 
+    #[test]
+    fn test_eval_while_statements() {
+        let test_strings = vec![
+            "let i = 0; while (i < 5) { i += 1; } i",
+            "while (false) { 1 }",
+            "let i = 0; let sum = 0; while (i < 10) { i += 1; if (i % 2 == 0) { continue; } sum += i; } sum",
+            "let i = 0; while (true) { if (i == 3) { break; } i += 1; } i",
+            // break は一番内側の loop だけを抜ける
+            "let n = 0; let i = 0; while (i < 3) { i += 1; let j = 0; while (true) { j += 1; if (j > 2) { break } n += 1; } } n",
+            "let f = fn() { let i = 0; while (true) { i += 1; if (i == 4) { return i * 10; } } }; f()",
+            // 再帰と違い, 繰り返しの回数で stack は深くならない
+            "let i = 0; while (i < 100000) { i += 1; } i",
+            // 値として使う if の中でも, その中の loop は抜けられる
+            "let i = 0; let x = if (true) { while (true) { i += 1; if (i == 3) { break } } i }; x",
+            // 文の先頭の if の後ろに演算子が続いても, break がなければ値になる
+            "let i = 0; while (i < 2) { i += 1; if (true) { 1 } + 1; } i",
+        ];
+        let expect_strings = vec!["5", "null", "25", "3", "6", "40", "100000", "3", "2"];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_statement_after_block() {
+        let test_strings = vec![
+            "let f = fn() { 1 }; [1, 2]",
+            "let f = fn() { 1 };\n[1, 2]",
+            "if (true) { [9] }; [0]",
+            "let f = fn() { 1 }; (f() + 2) * 3",
+            "let x = 1; if (x) { x = 2 }; (x)",
+            "let makeCounter = fn() { let n = 0; let inc = fn() { n += 1; n }; let get = fn() { n }; [inc, get] }; let c = makeCounter(); c[0](); c[0](); c[1]()",
+        ];
+        let expect_strings = vec!["[1, 2]", "[1, 2]", "[0]", "9", "2", "2"];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_closures_share_captured_bindings() {
+        let test_strings = vec![
+            // 同じ呼び出しで作られた closure は同じ変数を更新し, 読む
+            r#"let makeCounter = fn() { let n = 0; {"inc": fn() { n += 1; n }, "get": fn() { n }} }; let c = makeCounter(); c["inc"](); c["inc"](); c["get"]()"#,
+            // 呼び出しごとに別の変数ができる
+            r#"let makeCounter = fn() { let n = 0; {"inc": fn() { n += 1; n }, "get": fn() { n }} }; let c = makeCounter(); let d = makeCounter(); c["inc"](); c["inc"](); d["inc"](); [c["get"](), d["get"]()]"#,
+            // 呼び出し元の変数の変更は, 後から呼んだ closure から見える
+            "let make = fn() { let n = 1; let get = fn() { n }; n = 5; get }; let get = make(); get()",
+            "let x = 1; let f = fn() { x }; x = 2; f()",
+            // 同じ環境での let の宣言しなおしも同じ変数を置き換える
+            "let x = 1; let f = fn() { x }; let x = 3; f()",
+            // 関数の中の let は closure の外側の変数を隠し, 更新しない
+            "let n = 0; let f = fn() { let n = 10; n += 1; n }; let r = f(); [r, n]",
+            // 配列は値として共有されるので, 要素の代入も closure の間で見える
+            "let f = fn() { let a = [0]; let inc = fn() { a[0] += 1 }; inc(); inc(); a }; f()",
+            // loop の closure は反復ごとの変数を別々に持つ
+            "let fs = []; for (i in range(3)) { fs = push(fs, fn() { i += 10; i }) } let a = fs[0](); let b = fs[0](); let c = fs[2](); [a, b, c]",
+            // 引数は呼び出しごとの変数で, 呼び出し元の変数は変わらない
+            "let x = 1; let f = fn(x) { x += 1; x }; let r = f(x); [r, x]",
+        ];
+        let expect_strings = vec![
+            "2",
+            "[2, 1]",
+            "5",
+            "2",
+            "3",
+            "[11, 0]",
+            "[2]",
+            "[10, 20, 12]",
+            "[2, 1]",
+        ];
+
+        test_eval(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_const_statements() {
+        let test_strings = vec![
+            "const a = 5; a * 2",
+            "const a = [1, 2]; let b = a; b[0] = 10; [a, b]",
+            // 関数の引数や中の宣言で隠すことはできる
+            "const x = 1; let f = fn(x) { x = 2; x }; let r = f(0); [r, x]",
+            "const x = 1; let f = fn() { let x = 2; x += 1; x }; let r = f(); [r, x]",
+            // loop の body は反復ごとの scope なので, 毎回宣言できる
+            "let sum = 0; for (x in range(3)) { const y = x * 2; sum += y } sum",
+            "let i = 0; let x = 10; while (i < 3) { const x = i; i += 1 } [i, x]",
+        ];
+        let expect_strings = vec![
+            "10",
+            "[[1, 2], [10, 2]]",
+            "[2, 1]",
+            "[3, 1]",
+            "6",
+            "[3, 10]",
+        ];
+
+        test_eval(expect_strings, test_strings);
+
+        // if と else の分岐では, それぞれ同じ名前を宣言できる
+        let test_strings = vec![
+            "let pick = fn(flag) { if (flag) { const limit = 1; limit } else { const limit = 2; limit } }; [pick(true), pick(false)]",
+            "let x = 1; if (x == 0) { const y = 1 } else { let y = 2; y = 3 }; y",
+        ];
+        test_eval(vec!["[1, 2]", "3"], test_strings);
+
+        // 宣言より前に定義した関数からの代入は parser では検出できない
+        test_eval_error(
+            vec![
+                "1:16: cannot assign to constant: c",
+                "1:16: cannot assign to constant: a",
+            ],
+            vec![
+                "let f = fn() { c = 2 }; const c = 1; f()",
+                "let f = fn() { a[0] += 1 }; const a = [1]; f()",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_eval_for_statements() {
+        let test_strings = vec![
+            "for (x in [1, 2]) { x }",
+            "let f = fn(xs) { for (x in xs) { if (x > 2) { return x } } }; f([1, 2, 3, 4])",
+            r#"let f = fn() { for (i in range(10)) { if (i == 3) { break; } if (i == 5) { return "no" } } "yes" }; f()"#,
+            "let f = fn() { for (i in range(10)) { if (i < 7) { continue; } return i } }; f()",
+            r#"let f = fn(s) { for (i, c in s) { if (c == "c") { return [i, c] } } }; f("abc")"#,
+            r#"let f = fn(h) { for (k, v in h) { if (v == 2) { return k } } }; f({"a": 1, "b": 2})"#,
+            r#"let f = fn(h) { for (v in h) { return v } }; f({"a": 1})"#,
+            "let f = fn() { for (i, n in range(2, 5)) { if (i == 1) { return [i, n] } } }; f()",
+            "let f = fn() { for (x in range(3, 1)) { return x } }; f()",
+            // body で作った closure は, その回の変数を捕まえる
+            "let f = fn() { for (x in [1, 2, 3]) { let g = fn() { x * 10 }; if (x == 2) { return g } } }; f()()",
+            // loop の変数は外側の同じ名前を隠すだけで, 上書きしない
+            "let x = 0; for (x in [1, 2]) { x }; x",
+        ];
+        let expect_strings = vec![
+            "null",
+            "3",
+            "yes",
+            "7",
+            r#"[2, "c"]"#,
+            "b",
+            "1",
+            "[1, 3]",
+            "null",
+            "20",
+            "0",
+        ];
+
+        test_eval(expect_strings, test_strings);
+
+        test_eval_error(
+            vec![
+                "1:11: cannot iterate over integer",
+                "1:23: identifier not found: x",
+            ],
+            vec!["for (x in 5) { x }", "for (x in [1]) { x }; x"],
+        );
+    }
+
+    #[test]
+    fn test_eval_assignments() {
+        let test_strings = vec![
+            "let x = 1; x = 2; x",
+            "let x = 1; x += 2; x -= 5; x",
+            "let x = 1; let y = (x = 5); [x, y]",
+            "let a = 1; let b = 2; a = b = 3; [a, b]",
+            r#"let s = "a"; s += "b"; s"#,
+            // 外側の環境の変数を更新する
+            "let total = 0; let add = fn(n) { total += n; }; add(2); add(3); total",
+            "let counter = fn() { let c = 0; fn() { c += 1; c } }; let next = counter(); next(); next(); next()",
+            "let x = 1; let f = fn() { let x = 2; x = 3; x }; let r = f(); [r, x]",
+            "let i = 0; while (i < 3) { i += 1 } i",
+            "let sum = 0; for (x in range(5)) { sum += x } sum",
+            // 配列と hash の要素
+            "let a = [1, 2, 3]; a[1] = 20; a[2] += 1; a",
+            "let m = [[1, 2], [3]]; m[0][1] = 9; m",
+            r#"let h = {"a": 1}; h["b"] = 2; h["a"] += 10; h"#,
+            r#"let h = {"xs": [1]}; h["xs"][0] = "one"; h"#,
+            "let a = [1]; let b = a; b[0] = 2; [a, b]",
+            "let a = [0, 0]; for (i in range(2)) { a[i] = i + 1 } a",
+        ];
+        let expect_strings = vec![
+            "2",
+            "-2",
+            "[5, 5]",
+            "[3, 3]",
+            "ab",
+            "5",
+            "3",
+            "[3, 1]",
+            "3",
+            "10",
+            "[1, 20, 4]",
+            "[[1, 9], [3]]",
+            r#"{"a": 11, "b": 2}"#,
+            r#"{"xs": ["one"]}"#,
+            "[[1], [2]]",
+            "[1, 2]",
+        ];
+
+        test_eval(expect_strings, test_strings);
+
+        test_eval_error(
+            vec![
+                "1:1: cannot assign to undeclared variable: x",
+                "2:16: cannot assign to undeclared variable: y",
+                "1:16: index out of bounds: the length is 1 but the index is 1",
+                "1:16: index out of bounds: the length is 1 but the index is -1",
+                "1:15: cannot index string with integer",
+            ],
+            vec![
+                "x = 1",
+                "let x = 0;\nlet f = fn() { y += 1 }; f()",
+                "let a = [1]; a[1] = 2",
+                "let a = [1]; a[-1] = 2",
+                r#"let s = "ab"; s[0] = "c""#,
+            ],
+        );
+
+        test_eval_error_kind(
+            vec![
+                RuntimeErrorKind::TypeMismatch {
+                    operator: "+",
+                    left: "integer",
+                    right: "boolean",
+                },
+                RuntimeErrorKind::UnhashableKey("array"),
+            ],
+            vec!["let x = 1; x += true", "let h = {}; h[[1]] = 1"],
+        );
+    }
+
     #[test]
     fn test_eval_let_statements() {
         let test_strings = vec![
@@ -562,6 +1877,377 @@ mod test {
         test_eval(expect_strings, test_strings);
     }
 
+    #[test]
+    fn test_eval_error_positions() {
+        let test_strings = vec!["let a = 1;\nlet b = a + c;", "let a = 1;\n  a(2);"];
+        let expect_strings = vec!["2:13: identifier not found: c", "2:3: not a function: 1"];
+
+        test_eval_error(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_operator_type_errors() {
+        let operands = [
+            ("1", "integer"),
+            ("1.5", "float"),
+            ("true", "boolean"),
+            (r#""a""#, "string"),
+            ("[1]", "array"),
+            (r#"{"a": 1}"#, "hash"),
+            ("fn(x) { x }", "function"),
+            ("len", "builtin"),
+            ("(if (false) { 1 })", "null"),
+        ];
+        let infix_operators = ["+", "-", "*", "/", "%", "<", ">", "<=", ">=", "==", "!="];
+
+        let mut test_strings = Vec::new();
+        let mut expect_kinds = Vec::new();
+        for &(left, left_type) in operands.iter() {
+            for &(right, right_type) in operands.iter() {
+                for &operator in infix_operators.iter() {
+                    let supported = match (left_type, right_type) {
+                        ("integer", "integer")
+                        | ("integer", "float")
+                        | ("float", "integer")
+                        | ("float", "float") => true,
+                        ("boolean", "boolean") => operator == "==" || operator == "!=",
+                        ("string", "string") => ["+", "==", "!="].contains(&operator),
+                        _ => false,
+                    };
+                    if supported {
+                        continue;
+                    }
+                    test_strings.push(format!("{} {} {}", left, operator, right));
+                    expect_kinds.push(if left_type != right_type {
+                        RuntimeErrorKind::TypeMismatch {
+                            operator,
+                            left: left_type,
+                            right: right_type,
+                        }
+                    } else {
+                        RuntimeErrorKind::UnknownOperator {
+                            operator,
+                            left: Some(left_type),
+                            right: right_type,
+                        }
+                    });
+                }
+            }
+
+            for &operator in ["-", "!"].iter() {
+                if (operator, left_type) == ("-", "integer")
+                    || (operator, left_type) == ("-", "float")
+                    || (operator, left_type) == ("!", "boolean")
+                {
+                    continue;
+                }
+                test_strings.push(format!("{}{}", operator, left));
+                expect_kinds.push(RuntimeErrorKind::UnknownOperator {
+                    operator,
+                    left: None,
+                    right: left_type,
+                });
+            }
+        }
+
+        test_eval_error_kind(
+            expect_kinds,
+            test_strings.iter().map(String::as_str).collect(),
+        );
+    }
+
+    #[test]
+    fn test_eval_operator_error_messages() {
+        let test_strings = vec![
+            "5 + true;",
+            "5 + true; 5;",
+            "-true",
+            "!5",
+            "true + false;",
+            "let f = fn() { if (10 > 1) { return true + false; } };\nf();",
+            r#""a" - "b""#,
+            r#"{"a": 1} == {"a": 1}"#,
+        ];
+        let expect_strings = vec![
+            "1:1: type mismatch: integer + boolean",
+            "1:1: type mismatch: integer + boolean",
+            "1:1: unknown operator: -boolean",
+            "1:1: unknown operator: !integer",
+            "1:1: unknown operator: boolean + boolean",
+            "1:37: unknown operator: boolean + boolean",
+            "1:1: unknown operator: string - string",
+            "1:1: unknown operator: hash == hash",
+        ];
+
+        test_eval_error(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_division_by_zero() {
+        let test_strings = vec![
+            "1 / 0",
+            "let zero = 1 - 1;\n10 / zero",
+            "99999999999999999999 / 0",
+            "5 % 0",
+            "99999999999999999999 % 0",
+        ];
+        let expect_strings = vec![
+            "1:1: division by zero",
+            "2:1: division by zero",
+            "1:1: division by zero",
+            "1:1: division by zero",
+            "1:1: division by zero",
+        ];
+
+        test_eval_error(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_eval_float_expressions() {
+        let test_strings = vec![
+            "3.14",
+            ".5",
+            "1e-9",
+            "2.5E+3",
+            "1.0",
+            "-1.5",
+            ".5 + 1",
+            "7 / 2.0",
+            "7 / 2",
+            "0.1 + 0.2",
+            "1.5 * 2 - 3",
+            "1.0 / 0",
+            "-1 / 0.0",
+            "1.5 > 1",
+            "2 < 1.5",
+            "1 == 1.0",
+            "1.5 != 1.5",
+            "99999999999999999999 * 1.0",
+            "type(1.5)",
+            "[1.0, 2.5]",
+        ];
+        let expect_strings = vec![
+            "3.14",
+            "0.5",
+            "1e-9",
+            "2500.0",
+            "1.0",
+            "-1.5",
+            "1.5",
+            "3.5",
+            "3",
+            "0.30000000000000004",
+            "0.0",
+            "inf",
+            "-inf",
+            "true",
+            "false",
+            "true",
+            "false",
+            "1e20",
+            "float",
+            "[1.0, 2.5]",
+        ];
+
+        test_eval(expect_strings, test_strings);
+
+        test_eval_error_kind(
+            vec![RuntimeErrorKind::UnhashableKey("float")],
+            vec!["{1.5: 1}"],
+        );
+    }
+
+    #[test]
+    fn test_eval_big_integers() {
+        let test_strings = vec![
+            "2147483647 + 1",
+            "9223372036854775807",
+            "2 * 9223372036854775807",
+            "9223372036854775807 + 1",
+            "-9223372036854775807 - 2",
+            "let min = -9223372036854775807 - 1; [min, -min, min / -1]",
+            "123456789012345678901234567890",
+            "123456789012345678901234567890 * 0",
+            "(9223372036854775807 + 1) - 1",
+            "99999999999999999999 / 3",
+            "99999999999999999999 > 1",
+            "-99999999999999999999 < 1",
+            "99999999999999999999 == 99999999999999999999",
+            "(9223372036854775807 + 1) - 1 == 9223372036854775807",
+            "type(99999999999999999999)",
+            "0xFFFF_FFFF_FFFF_FFFF + 0b1",
+            r#"{99999999999999999999: "big"}[99999999999999999998 + 1]"#,
+            "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(25)",
+        ];
+        let expect_strings = vec![
+            "2147483648",
+            "9223372036854775807",
+            "18446744073709551614",
+            "9223372036854775808",
+            "-9223372036854775809",
+            "[-9223372036854775808, 9223372036854775808, 9223372036854775808]",
+            "123456789012345678901234567890",
+            "0",
+            "9223372036854775807",
+            "33333333333333333333",
+            "true",
+            "true",
+            "true",
+            "true",
+            "integer",
+            "18446744073709551616",
+            "big",
+            "15511210043330985984000000",
+        ];
+
+        test_eval(expect_strings, test_strings);
+        test_eval_error_kind(
+            vec![RuntimeErrorKind::IndexOutOfBounds {
+                index: "99999999999999999999".to_string(),
+                length: 1,
+            }],
+            vec!["[1][99999999999999999999]"],
+        );
+    }
+
+    #[test]
+    fn test_eval_error_kinds() {
+        let test_strings = vec![
+            "foo;",
+            "let f = 5; f(1);",
+            "let add = fn(x, y) { x + y }; add(1);",
+            "[1, 2, 3][3]",
+            "[1, 2, 3][-1]",
+            "1[0]",
+            "[1][true]",
+            r#"{"a": 1}[fn(x) { x }]"#,
+            "{[1]: 2}",
+        ];
+        let expect_kinds = vec![
+            RuntimeErrorKind::UnknownIdentifier("foo".to_string()),
+            RuntimeErrorKind::NotCallable("5".to_string()),
+            RuntimeErrorKind::ArityMismatch {
+                expected: 2,
+                actual: 1,
+            },
+            RuntimeErrorKind::IndexOutOfBounds {
+                index: "3".to_string(),
+                length: 3,
+            },
+            RuntimeErrorKind::IndexOutOfBounds {
+                index: "-1".to_string(),
+                length: 3,
+            },
+            RuntimeErrorKind::IndexNotSupported {
+                object: "integer",
+                index: "integer",
+            },
+            RuntimeErrorKind::IndexNotSupported {
+                object: "array",
+                index: "boolean",
+            },
+            RuntimeErrorKind::UnhashableKey("function"),
+            RuntimeErrorKind::UnhashableKey("array"),
+        ];
+
+        test_eval_error_kind(expect_kinds, test_strings);
+    }
+
+    #[test]
+    fn test_malformed_programs_do_not_panic() {
+        let test_strings = vec![
+            "let a = 1 @ 2;",
+            "let = 5;",
+            "if (true) { 1",
+            ")(",
+            "1 / 0",
+            "[1, 2][2]",
+            "foo(1)",
+            "let a = 5; a(1)",
+            "true(false)",
+            "let f = fn(x) { x }; f()",
+            "let f = fn(x) { x }; f(1, 2)",
+            "let f = fn() { f() }; f()",
+            "let f = fn(n) { 1 + f(n) }; f(1)",
+        ];
+
+        // どの段階のエラーになるか. 終わらない再帰も評価に使う stack の上限で止まるので,
+        // test thread の既定の stack で足りる
+        let expect_stages = vec![
+            "lex", "parse", "parse", "parse", "runtime", "runtime", "runtime", "runtime",
+            "runtime", "runtime", "runtime", "runtime", "runtime",
+        ];
+        let evaluator = Evaluator::new();
+        for (test_string, expect_stage) in test_strings.into_iter().zip(expect_stages) {
+            let stage = match crate::lexer::lex(test_string) {
+                Err(_) => "lex",
+                Ok(tokens) => match crate::parser::parse(tokens) {
+                    Err(_) => "parse",
+                    Ok(program) => {
+                        let mut global_env = Rc::new(RefCell::new(Enviroment::new()));
+                        match evaluator.eavl_program(&program, &mut global_env) {
+                            Err(_) => "runtime",
+                            Ok(_) => "ok",
+                        }
+                    }
+                },
+            };
+            assert_eq!(stage, expect_stage, "{}", test_string);
+        }
+    }
+
+    #[test]
+    fn test_eval_recursion_limit() {
+        let eval = |evaluator: &Evaluator, test_string: &str| {
+            let program = crate::parser::parse(crate::lexer::lex(test_string).unwrap()).unwrap();
+            let mut global_env = Rc::new(RefCell::new(Enviroment::new()));
+            evaluator.eavl_program(&program, &mut global_env)
+        };
+        let count_down = "let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } };";
+
+        // 上限は関数呼び出しの数で数える. f(n) は n + 1 回呼び出す
+        let mut evaluator = Evaluator::new();
+        evaluator.set_max_call_depth(20);
+        let result = eval(&evaluator, &format!("{} f(19)", count_down));
+        assert_eq!(result.unwrap().literal(), "19");
+        let error = eval(&evaluator, &format!("{} f(20)", count_down))
+            .err()
+            .unwrap();
+        assert_eq!(error.kind, RuntimeErrorKind::RecursionLimitExceeded(20));
+        assert_eq!(
+            error.to_string(),
+            "1:46: maximum recursion depth of 20 exceeded"
+        );
+
+        // 上限より先に stack を使い切るときは, 既定の stack の thread のまま止まる
+        let mut evaluator = Evaluator::new();
+        evaluator.set_max_call_depth(usize::MAX);
+        let error = eval(&evaluator, "let f = fn(n) { f(n + 1) }; f(0)")
+            .err()
+            .unwrap();
+        assert_eq!(error.kind, RuntimeErrorKind::StackExhausted);
+
+        // 既定の上限までの再帰は, 大きな stack の thread で stack の上限を広げれば評価できる
+        const STACK_SIZE: usize = 256 * 1024 * 1024;
+        let handle = std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || {
+                let mut evaluator = Evaluator::new();
+                evaluator.set_stack_budget(STACK_SIZE - 4 * 1024 * 1024);
+                let result = eval(&evaluator, &format!("{} f(999)", count_down));
+                assert_eq!(result.unwrap().literal(), "999");
+                let error = eval(&evaluator, &format!("{} f(1000)", count_down))
+                    .err()
+                    .unwrap();
+                assert_eq!(
+                    error.kind,
+                    RuntimeErrorKind::RecursionLimitExceeded(DEFAULT_MAX_CALL_DEPTH)
+                );
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
     #[test]
     fn test_eval_function_applications() {
         let test_strings = vec![
@@ -572,7 +2258,7 @@ mod test {
             "let add = fn(x, y) { x + y; }; add(5 + 5, add(10, 10));",
             "let add = fn(a, b) { a + b; }; 
             let applyFunc = fn(a, b, func) { func(a, b) };
-            applyFunc(10, 2, add);"
+            applyFunc(10, 2, add);",
         ];
         let expect_strings = vec!["5", "10", "20", "15", "30", "12"];
 
@@ -0,0 +1,385 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::env::Enviroment;
+use crate::error::{Error, RuntimeError, RuntimeErrorKind};
+use crate::evaluator::Evaluator;
+use crate::object::Object;
+use crate::token::Position;
+use crate::{lexer, parser};
+
+// Rust の application に Monkey を埋め込むための入口
+// lexer, parser, evaluator を順に呼び, 評価をまたいで global な環境を保持する
+pub struct Interpreter {
+    // register_fn で登録した関数. reset しても残るよう global の外側に置く
+    host_env: Rc<RefCell<Enviroment>>,
+    env: Rc<RefCell<Enviroment>>,
+    evaluator: Evaluator,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let host_env = Rc::new(RefCell::new(Enviroment::new()));
+        Interpreter {
+            env: global_env(&host_env),
+            host_env,
+            evaluator: Evaluator::new(),
+        }
+    }
+
+    pub fn eval_str(&mut self, source: &str) -> Result<Object, Error> {
+        self.eval_str_from(source, Position::default())
+    }
+
+    // source が start の位置から始まるものとして評価する. 入力をつなげて読む REPL などで
+    // エラーの位置を入力をまたいで通しで数えるために使う
+    pub fn eval_str_from(&mut self, source: &str, start: Position) -> Result<Object, Error> {
+        let tokens = lexer::lex_from(source, start)?;
+        let program = parser::parse(tokens)?;
+        Ok(self.evaluator.eavl_program(&program, &mut self.env)?)
+    }
+
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Object, Error> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|error| Error::Io {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        })?;
+        self.eval_str(&source)
+    }
+
+    // 関数呼び出しをいくつまで入れ子にできるか. 既定は evaluator::DEFAULT_MAX_CALL_DEPTH
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.evaluator.set_max_call_depth(max_call_depth);
+    }
+
+    // 評価に使ってよい stack の大きさ (byte). 既定は evaluator::DEFAULT_STACK_BUDGET で,
+    // 深い再帰を評価するときは大きな stack の thread で評価し, この値も大きくする
+    pub fn set_stack_budget(&mut self, stack_budget: usize) {
+        self.evaluator.set_stack_budget(stack_budget);
+    }
+
+    // global な環境に束縛された名前. register_fn で登録した関数は含めない
+    pub fn global_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .env
+            .borrow()
+            .store_iter()
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.env.borrow().get(name)
+    }
+
+    // global な変数を束縛する. const で束縛された名前は置き換えられない
+    pub fn set_global(&mut self, name: &str, value: Object) -> Result<(), RuntimeError> {
+        self.env
+            .borrow_mut()
+            .set(name, value)
+            .map_err(|_| RuntimeError::new(RuntimeErrorKind::AssignToConstant(name.to_string())))
+    }
+
+    // 代入も宣言しなおしもできない global な変数を束縛する
+    pub fn set_global_const(&mut self, name: &str, value: Object) -> Result<(), RuntimeError> {
+        self.env
+            .borrow_mut()
+            .set_const(name, value)
+            .map_err(|_| RuntimeError::new(RuntimeErrorKind::RedeclareConstant(name.to_string())))
+    }
+
+    // global な変数が変更できるか. 束縛されていなければ None
+    pub fn is_global_mutable(&self, name: &str) -> Option<bool> {
+        self.env.borrow().is_mutable(name)
+    }
+
+    // global な変数をすべて捨てる. register_fn で登録した関数は残す
+    pub fn reset(&mut self) {
+        self.env = global_env(&self.host_env);
+    }
+
+    // Rust の関数を global な環境に name で登録する. 引数の数は arity と一致していることを確認してから呼ぶ
+    pub fn register_fn<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Object]) -> Result<Object, RuntimeError> + 'static,
+    {
+        // host_env には const を置かないので束縛は失敗しない
+        let _ = self.host_env.borrow_mut().set(
+            name,
+            Object::HostFunction {
+                name: name.to_string(),
+                arity,
+                function: Rc::new(function),
+            },
+        );
+    }
+}
+
+fn global_env(host_env: &Rc<RefCell<Enviroment>>) -> Rc<RefCell<Enviroment>> {
+    let mut env = Enviroment::new();
+    env.add_outer(host_env);
+    Rc::new(RefCell::new(env))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::ParseErrorKind;
+
+    #[test]
+    fn test_register_fn() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_fn("double", 1, |arguments| match &arguments[0] {
+            Object::Integer { value } => Ok(Object::Integer { value: value * 2 }),
+            object => Err(RuntimeError::new(RuntimeErrorKind::InvalidArgument {
+                function: "double",
+                expected: "integer",
+                actual: object.type_name(),
+            })),
+        });
+
+        let result = interpreter.eval_str("let x = double(21); x").unwrap();
+        assert_eq!(result.literal(), "42");
+        let result = interpreter.eval_str("type(double)").unwrap();
+        assert_eq!(result.literal(), "builtin");
+
+        let error = interpreter.eval_str("double(1, 2)").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "runtime error: 1:1: wrong number of arguments: expected 1, got 2"
+        );
+        let error = interpreter.eval_str("\n  double(true)").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "runtime error: 2:3: argument to `double` must be integer, got boolean"
+        );
+    }
+
+    #[test]
+    fn test_register_fn_with_state() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut interpreter = Interpreter::new();
+        let sink = Rc::clone(&log);
+        interpreter.register_fn("log", 1, move |arguments| {
+            sink.borrow_mut().push(arguments[0].literal());
+            Ok(Object::Null)
+        });
+
+        interpreter
+            .eval_str(r#"let f = fn(x) { log("got " + str(x)) }; f(1); f([2]);"#)
+            .unwrap();
+        assert_eq!(*log.borrow(), vec!["got 1", "got [2]"]);
+
+        // 同じ名前で登録しなおすと上書きされ, Monkey 側の let でも上書きできる
+        interpreter.register_fn("log", 0, |_| Ok(Object::Integer { value: 1 }));
+        assert_eq!(interpreter.eval_str("log()").unwrap().literal(), "1");
+        interpreter.eval_str("let log = 5;").unwrap();
+        assert_eq!(interpreter.eval_str("log").unwrap().literal(), "5");
+
+        // reset しても登録した関数は残る
+        interpreter.reset();
+        assert_eq!(interpreter.eval_str("log()").unwrap().literal(), "1");
+    }
+
+    #[test]
+    fn test_for_closures_capture_each_iteration() {
+        let kept = Rc::new(RefCell::new(Vec::new()));
+        let mut interpreter = Interpreter::new();
+        let sink = Rc::clone(&kept);
+        interpreter.register_fn("keep", 1, move |arguments| {
+            sink.borrow_mut().push(arguments[0].clone());
+            Ok(Object::Null)
+        });
+
+        interpreter
+            .eval_str("for (i, x in [10, 20, 30]) { keep(fn() { [i, x] }) }")
+            .unwrap();
+        let closures = kept.borrow().clone();
+        interpreter
+            .set_global("closures", Object::Array { elements: closures })
+            .unwrap();
+        let result = interpreter
+            .eval_str("[closures[0](), closures[1](), closures[2]()]")
+            .unwrap();
+        assert_eq!(result.literal(), "[[0, 10], [1, 20], [2, 30]]");
+    }
+
+    #[test]
+    fn test_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let a = 1;").unwrap();
+        interpreter.eval_str("let add = fn(x) { x + a };").unwrap();
+        interpreter
+            .set_global(
+                "b",
+                Object::String {
+                    value: "bee".to_string(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(interpreter.get_global("a").unwrap().literal(), "1");
+        assert_eq!(interpreter.get_global("b").unwrap().literal(), "bee");
+        assert!(interpreter.get_global("c").is_none());
+        assert_eq!(interpreter.eval_str("add(41)").unwrap().literal(), "42");
+        assert_eq!(interpreter.eval_str("b + b").unwrap().literal(), "beebee");
+
+        assert_eq!(interpreter.global_names(), vec!["a", "add", "b"]);
+
+        // 前の入力の続きとして評価すると, エラーの位置も続きから数える
+        let start = Position {
+            offset: 10,
+            line: 3,
+            column: 1,
+        };
+        let error = interpreter.eval_str_from("a + c", start).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "runtime error: 3:5: identifier not found: c"
+        );
+
+        interpreter.reset();
+        assert!(interpreter.global_names().is_empty());
+        assert!(interpreter.get_global("a").is_none());
+        assert!(matches!(
+            interpreter.eval_str("add(1)"),
+            Err(Error::Runtime(_))
+        ));
+    }
+
+    #[test]
+    fn test_closures_update_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let n = 0; let inc = fn() { n += 1; n };")
+            .unwrap();
+        interpreter.eval_str("inc(); inc();").unwrap();
+        assert_eq!(interpreter.get_global("n").unwrap().literal(), "2");
+
+        // host から書き換えた値も closure から見える
+        interpreter
+            .set_global("n", Object::Integer { value: 40 })
+            .unwrap();
+        assert_eq!(interpreter.eval_str("inc() + 1").unwrap().literal(), "42");
+    }
+
+    #[test]
+    fn test_global_consts() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let a = 1; const b = 2;").unwrap();
+        interpreter
+            .set_global_const("c", Object::Integer { value: 3 })
+            .unwrap();
+
+        assert_eq!(interpreter.is_global_mutable("a"), Some(true));
+        assert_eq!(interpreter.is_global_mutable("b"), Some(false));
+        assert_eq!(interpreter.is_global_mutable("c"), Some(false));
+        assert_eq!(interpreter.is_global_mutable("d"), None);
+        assert_eq!(interpreter.eval_str("a + b + c").unwrap().literal(), "6");
+
+        // 前の評価で宣言された const は parser からは見えないので, 実行時に検出する
+        let error = interpreter.eval_str("b = 5").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "runtime error: 1:1: cannot assign to constant: b"
+        );
+        let error = interpreter.eval_str("let c = 5").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "runtime error: 1:5: cannot redeclare constant: c"
+        );
+
+        // host からも const は置き換えられない
+        let error = interpreter
+            .set_global("b", Object::Integer { value: 5 })
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "cannot assign to constant: b");
+        let error = interpreter
+            .set_global_const("c", Object::Integer { value: 5 })
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "cannot redeclare constant: c");
+        interpreter
+            .set_global("a", Object::Integer { value: 5 })
+            .unwrap();
+
+        assert_eq!(interpreter.get_global("a").unwrap().literal(), "5");
+        assert_eq!(interpreter.get_global("b").unwrap().literal(), "2");
+        assert_eq!(interpreter.get_global("c").unwrap().literal(), "3");
+    }
+
+    #[test]
+    fn test_deeply_nested_while() {
+        // test thread の既定の stack のまま, 入れ子の上限で止まる
+        let mut interpreter = Interpreter::new();
+        let unclosed = "while (false) { ".repeat(3000);
+        let closed = format!("{}{}", unclosed, "}".repeat(3000));
+        for source in [unclosed, closed] {
+            match interpreter.eval_str(&source) {
+                Err(Error::Parse(errors)) => {
+                    assert_eq!(errors[0].kind, ParseErrorKind::NestingTooDeep)
+                }
+                result => panic!("unexpected result: {:?}", result.map(|o| o.literal())),
+            }
+        }
+
+        let source = format!(
+            "let i = 0; {}i += 1{} i",
+            "while (i < 1) { ".repeat(100),
+            " }".repeat(100)
+        );
+        assert_eq!(interpreter.eval_str(&source).unwrap().literal(), "1");
+    }
+
+    #[test]
+    fn test_deeply_nested_for() {
+        let mut interpreter = Interpreter::new();
+        let unclosed = "for (x in [1]) { ".repeat(3000);
+        let closed = format!("{}{}", unclosed, "}".repeat(3000));
+        for source in [unclosed, closed] {
+            match interpreter.eval_str(&source) {
+                Err(Error::Parse(errors)) => {
+                    assert_eq!(errors[0].kind, ParseErrorKind::NestingTooDeep)
+                }
+                result => panic!("unexpected result: {:?}", result.map(|o| o.literal())),
+            }
+        }
+
+        let source = format!(
+            "let n = 0; {}n += x{} n",
+            "for (x in [1]) { ".repeat(100),
+            " }".repeat(100)
+        );
+        assert_eq!(interpreter.eval_str(&source).unwrap().literal(), "1");
+    }
+
+    #[test]
+    fn test_eval_file() {
+        let path = std::env::temp_dir().join("monkey_interpreter_test_eval_file.mk");
+        std::fs::write(&path, "let x = 20;\nx * 2 + 2").unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap().literal(), "42");
+        assert_eq!(interpreter.get_global("x").unwrap().literal(), "20");
+
+        let error = interpreter.eval_file("no/such/file.mk").err().unwrap();
+        assert!(matches!(error, Error::Io { .. }));
+        assert!(error
+            .to_string()
+            .starts_with("cannot read no/such/file.mk: "));
+    }
+}
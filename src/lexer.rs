@@ -1,8 +1,13 @@
-use crate::token::Token;
+use crate::bigint::BigInt;
+use crate::error::{LexError, LexErrorKind};
+use crate::token::{Comment, Position, Span, SpannedToken, Token};
 
 struct Lexer {
     input: Vec<char>,
     next_read_index: usize,
+    position: Position,
+    // 読み飛ばした comment. formatter などで元の comment を残せるようにする
+    comments: Vec<Comment>,
 }
 
 impl Lexer {
@@ -10,18 +15,22 @@ impl Lexer {
         Lexer {
             input: input.chars().collect(),
             next_read_index: 0,
+            position: Position::default(),
+            comments: Vec::new(),
         }
     }
 
     fn read_char(&mut self) -> Option<char> {
-        let return_value: Option<char>;
         if self.next_read_index < self.input.len() {
-            return_value = Some(self.input[self.next_read_index]);
+            let c = self.input[self.next_read_index];
             self.next_read_index += 1;
+
+            // 次に読む文字の位置を進める
+            self.position.advance(c);
+            Some(c)
         } else {
-            return_value = None;
-        };
-        return_value
+            None
+        }
     }
 
     fn peek_char(&self) -> Option<char> {
@@ -32,14 +41,56 @@ impl Lexer {
         }
     }
 
-    fn read_token(&mut self) -> Token {
-        self.skip_whitespace();
+    // n 文字先を読む (peek_nth_char(0) は peek_char と同じ)
+    fn peek_nth_char(&self, n: usize) -> Option<char> {
+        self.input.get(self.next_read_index + n).copied()
+    }
+
+    fn read_all_tokens(&mut self) -> Result<Vec<SpannedToken>, LexError> {
+        let mut token_vec: Vec<SpannedToken> = Vec::new();
+        let mut token = self.read_spanned_token()?;
+        while token.token != Token::Eof {
+            token_vec.push(token);
+            token = self.read_spanned_token()?;
+        }
+        Ok(token_vec)
+    }
+
+    fn read_spanned_token(&mut self) -> Result<SpannedToken, LexError> {
+        self.skip_trivia()?;
+
+        let start = self.position;
+        let token = self.read_token()?;
+        Ok(SpannedToken {
+            token,
+            span: Span::new(start, self.position),
+        })
+    }
 
-        match self.read_char() {
-            Some('+') => Token::Plus,
-            Some('-') => Token::Minus,
+    fn read_token(&mut self) -> Result<Token, LexError> {
+        self.skip_trivia()?;
+
+        let start = self.position;
+        let token = match self.read_char() {
+            Some('+') => {
+                if self.peek_char() == Some('=') {
+                    self.read_char();
+                    Token::PlusAssign
+                } else {
+                    Token::Plus
+                }
+            }
+            Some('-') => {
+                if self.peek_char() == Some('=') {
+                    self.read_char();
+                    Token::MinusAssign
+                } else {
+                    Token::Minus
+                }
+            }
             Some('*') => Token::Asterisk,
             Some('/') => Token::Slash,
+            Some('%') => Token::Percent,
             Some('!') => {
                 if self.peek_char() == Some('=') {
                     self.read_char();
@@ -58,33 +109,60 @@ impl Lexer {
             }
             Some(',') => Token::Comma,
             Some(';') => Token::SemiColon,
-            Some('<') => Token::Lt,
-            Some('>') => Token::Gt,
+            Some(':') => Token::Colon,
+            Some('<') => {
+                if self.peek_char() == Some('=') {
+                    self.read_char();
+                    Token::LtEq
+                } else {
+                    Token::Lt
+                }
+            }
+            Some('>') => {
+                if self.peek_char() == Some('=') {
+                    self.read_char();
+                    Token::GtEq
+                } else {
+                    Token::Gt
+                }
+            }
+            // `&` と `|` は 2 つ重ねた形だけを token にする
+            Some('&') if self.peek_char() == Some('&') => {
+                self.read_char();
+                Token::And
+            }
+            Some('|') if self.peek_char() == Some('|') => {
+                self.read_char();
+                Token::Or
+            }
             Some('(') => Token::LParen,
             Some(')') => Token::RParen,
             Some('{') => Token::LBrace,
             Some('}') => Token::RBrace,
+            Some('[') => Token::LBracket,
+            Some(']') => Token::RBracket,
+            Some('"') => self.lex_string_token(start)?,
             Some(c) => {
                 if Lexer::is_letter(c) {
                     self.lex_keyword_iden_token(c)
-                } else if Lexer::is_digit(c) {
-                    self.lex_int_token(c)
+                } else if Lexer::is_digit(c) || (c == '.' && self.starts_digit(0)) {
+                    self.lex_number_token(c, start)?
                 } else {
-                    Token::Illegal
+                    return Err(LexError::new(
+                        LexErrorKind::IllegalCharacter(c),
+                        Span::new(start, self.position),
+                    ));
                 }
             }
             None => Token::Eof,
-        }
+        };
+        Ok(token)
     }
 
     fn lex_keyword_iden_token(&mut self, c: char) -> Token {
         let mut literal: Vec<char> = Vec::new();
         literal.push(c);
-        while self
-            .peek_char()
-            .map(|c| Lexer::is_letter(c))
-            .unwrap_or(false)
-        {
+        while self.peek_char().map(Lexer::is_letter).unwrap_or(false) {
             literal.push(self.read_char().unwrap());
         }
         if let Some(token) = Token::lookup_keyword(&literal) {
@@ -94,25 +172,242 @@ impl Lexer {
         }
     }
 
-    fn lex_int_token(&mut self, c: char) -> Token {
+    // 123, 1_000, 3.14, .5, 1e-9 のような数値. 小数点か指数を含めば Token::Float
+    // 0x, 0o, 0b で始まれば 16, 8, 2 進数の整数
+    fn lex_number_token(&mut self, c: char, start: Position) -> Result<Token, LexError> {
+        if c == '0' {
+            if let Some(radix) = self.peek_char().and_then(Lexer::radix_of_prefix) {
+                self.read_char();
+                return self.lex_radix_int_token(radix, start);
+            }
+        }
+
+        // c は読んだ後なので, token は 1 文字前から始まる
+        let start_index = self.next_read_index - 1;
+
+        // `.5` の形は値の直後には書けない (e.g. `xs[0].5`, `f().5`)
+        let mut is_float = c == '.';
+        let follows_value = start_index
+            .checked_sub(1)
+            .map(|i| self.input[i])
+            .map(|prev| {
+                prev == ')' || prev == ']' || Lexer::is_letter(prev) || Lexer::is_digit(prev)
+            })
+            .unwrap_or(false);
+        if is_float && follows_value {
+            return Err(self.malformed_number(start_index, start));
+        }
+
         let mut literal: Vec<char> = Vec::new();
         literal.push(c);
+        self.read_digits(&mut literal);
+
+        // 小数部
+        if !is_float && self.peek_char() == Some('.') && self.starts_digit(1) {
+            is_float = true;
+            literal.push(self.read_char().unwrap());
+            self.read_digits(&mut literal);
+        }
+
+        // 指数部. `1e` や `1e+` のように数字が続かなければ error
+        if matches!(self.peek_char(), Some('e') | Some('E')) {
+            let sign = matches!(self.peek_nth_char(1), Some('+') | Some('-'));
+            let digit_index = if sign { 2 } else { 1 };
+            if !self.starts_digit(digit_index) {
+                return Err(self.malformed_number(start_index, start));
+            }
+            is_float = true;
+            for _ in 0..digit_index {
+                literal.push(self.read_char().unwrap());
+            }
+            self.read_digits(&mut literal);
+        }
+
+        // 数値の直後の `.` は, 2 つ目の小数点 (e.g. `1.5.2`) か数字のない小数部 (e.g. `1.`)
+        if self.peek_char() == Some('.') {
+            return Err(self.malformed_number(start_index, start));
+        }
+
+        // `_` は桁区切りで値には含めない
+        literal.retain(|&c| c != '_');
+        if is_float {
+            let text = literal.iter().collect::<String>();
+            match text.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(Token::Float(literal)),
+                _ => Err(LexError::new(
+                    LexErrorKind::FloatOutOfRange(
+                        self.input[start_index..self.next_read_index]
+                            .iter()
+                            .collect(),
+                    ),
+                    Span::new(start, self.position),
+                )),
+            }
+        } else {
+            let digits = literal.iter().collect::<String>();
+            Ok(Token::Int(BigInt::parse_radix(&digits, 10).unwrap()))
+        }
+    }
+
+    // prefix の直後から読む. 英数字が続く限り token の一部とし, radix 進数の数字でなければ error
+    fn lex_radix_int_token(&mut self, radix: u32, start: Position) -> Result<Token, LexError> {
+        let mut digits = String::new();
+        while let Some(c) = self
+            .peek_char()
+            .filter(|&c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            let digit_start = self.position;
+            self.read_char();
+            if c == '_' {
+                continue;
+            }
+            if !c.is_digit(radix) {
+                return Err(LexError::new(
+                    LexErrorKind::InvalidDigit { digit: c, radix },
+                    Span::new(digit_start, self.position),
+                ));
+            }
+            digits.push(c);
+        }
+
+        match BigInt::parse_radix(&digits, radix) {
+            Some(value) => Ok(Token::Int(value)),
+            None => Err(LexError::new(
+                LexErrorKind::MissingDigits { radix },
+                Span::new(start, self.position),
+            )),
+        }
+    }
+
+    // 数値として読めない token を, 続く英数字と `.` まで読み進めて error にする
+    fn malformed_number(&mut self, start_index: usize, start: Position) -> LexError {
         while self
             .peek_char()
-            .map(|c| Lexer::is_digit(c))
+            .map(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            .unwrap_or(false)
+        {
+            self.read_char();
+        }
+        let literal = self.input[start_index..self.next_read_index]
+            .iter()
+            .collect();
+        LexError::new(
+            LexErrorKind::MalformedNumber(literal),
+            Span::new(start, self.position),
+        )
+    }
+
+    // 数字と桁区切りの `_` を読む
+    fn read_digits(&mut self, literal: &mut Vec<char>) {
+        while self
+            .peek_char()
+            .map(|c| Lexer::is_digit(c) || c == '_')
             .unwrap_or(false)
         {
             literal.push(self.read_char().unwrap());
         }
-        Token::Int(literal)
+    }
+
+    fn radix_of_prefix(c: char) -> Option<u32> {
+        match c {
+            'x' => Some(16),
+            'o' => Some(8),
+            'b' => Some(2),
+            _ => None,
+        }
+    }
+
+    fn starts_digit(&self, n: usize) -> bool {
+        self.peek_nth_char(n).map(Lexer::is_digit).unwrap_or(false)
+    }
+
+    fn lex_string_token(&mut self, start: Position) -> Result<Token, LexError> {
+        let mut literal: Vec<char> = Vec::new();
+        loop {
+            let escape_start = self.position;
+            match self.read_char() {
+                Some('"') => return Ok(Token::Str(literal)),
+                Some('\\') => literal.push(self.read_escape(escape_start)?),
+                Some(c) => literal.push(c),
+                None => {
+                    return Err(LexError::new(
+                        LexErrorKind::UnterminatedString,
+                        Span::new(start, self.position),
+                    ))
+                }
+            }
+        }
+    }
+
+    // `\` の直後から読み, escape sequence が表す文字を返す
+    fn read_escape(&mut self, start: Position) -> Result<char, LexError> {
+        let c = match self.read_char() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('u') => return self.read_unicode_escape(start),
+            Some(c) => {
+                return Err(LexError::new(
+                    LexErrorKind::UnknownEscape(c),
+                    Span::new(start, self.position),
+                ))
+            }
+            None => {
+                return Err(LexError::new(
+                    LexErrorKind::UnterminatedString,
+                    Span::new(start, self.position),
+                ))
+            }
+        };
+        Ok(c)
+    }
+
+    // \u{1F600} のように 1 から 6 桁の 16 進数で code point を指定する
+    fn read_unicode_escape(&mut self, start: Position) -> Result<char, LexError> {
+        if self.peek_char() != Some('{') {
+            return Err(LexError::new(
+                LexErrorKind::InvalidUnicodeEscape(String::new()),
+                Span::new(start, self.position),
+            ));
+        }
+        self.read_char();
+
+        let mut digits = String::new();
+        while let Some(c) = self.peek_char() {
+            if c == '}' || c == '"' || digits.len() > 6 {
+                break;
+            }
+            digits.push(c);
+            self.read_char();
+        }
+        let closed = self.peek_char() == Some('}');
+        if closed {
+            self.read_char();
+        }
+
+        let c = if closed && !digits.is_empty() && digits.len() <= 6 {
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+        } else {
+            None
+        };
+        c.ok_or_else(|| {
+            LexError::new(
+                LexErrorKind::InvalidUnicodeEscape(digits),
+                Span::new(start, self.position),
+            )
+        })
     }
 
     fn is_letter(c: char) -> bool {
-        ('a' <= c && c <= 'z') || ('A' <= c && c <= 'Z') || (c == '_')
+        c.is_ascii_lowercase() || c.is_ascii_uppercase() || (c == '_')
     }
 
     fn is_digit(c: char) -> bool {
-        '0' <= c && c <= '9'
+        c.is_ascii_digit()
     }
 
     fn is_whitespace(c: char) -> bool {
@@ -120,35 +415,102 @@ impl Lexer {
     }
 
     fn skip_whitespace(&mut self) {
-        while self
-            .peek_char()
-            .map(|c| Lexer::is_whitespace(c))
-            .unwrap_or(false)
-        {
+        while self.peek_char().map(Lexer::is_whitespace).unwrap_or(false) {
             self.read_char();
         }
     }
+
+    // 空白と comment を読み飛ばす
+    fn skip_trivia(&mut self) -> Result<(), LexError> {
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            let mut text = String::new();
+            match (self.peek_char(), self.peek_nth_char(1)) {
+                (Some('/'), Some('/')) => self.read_line_comment(&mut text),
+                (Some('/'), Some('*')) => self.read_block_comment(start, &mut text)?,
+                _ => return Ok(()),
+            }
+            self.comments.push(Comment {
+                text,
+                span: Span::new(start, self.position),
+            });
+        }
+    }
+
+    // 行末まで読む. 改行は comment に含めない
+    fn read_line_comment(&mut self, text: &mut String) {
+        while let Some(c) = self.peek_char().filter(|&c| c != '\n') {
+            self.read_char();
+            text.push(c);
+        }
+    }
+
+    // `/*` から対応する `*/` まで読む. `/* /* */ */` のように入れ子にできる
+    fn read_block_comment(&mut self, start: Position, text: &mut String) -> Result<(), LexError> {
+        let mut depth = 0;
+        loop {
+            match (self.peek_char(), self.peek_nth_char(1)) {
+                (Some('/'), Some('*')) => {
+                    depth += 1;
+                    text.push_str("/*");
+                    self.read_char();
+                    self.read_char();
+                }
+                (Some('*'), Some('/')) => {
+                    depth -= 1;
+                    text.push_str("*/");
+                    self.read_char();
+                    self.read_char();
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                (Some(c), _) => {
+                    text.push(c);
+                    self.read_char();
+                }
+                (None, _) => {
+                    return Err(LexError::new(
+                        LexErrorKind::UnterminatedComment,
+                        Span::new(start, self.position),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+pub fn lex(input: &str) -> Result<Vec<SpannedToken>, LexError> {
+    lex_from(input, Position::default())
 }
 
-pub fn lex(input: &str) -> Vec<Token> {
+// input が start の位置から始まるものとして lex する (REPL で入力をまたいで位置を通すため)
+pub fn lex_from(input: &str, start: Position) -> Result<Vec<SpannedToken>, LexError> {
     let mut lexer = Lexer::new(input);
-    let mut token_vec: Vec<Token> = Vec::new();
-    let mut token = lexer.read_token();
-    while token != Token::Eof {
-        token_vec.push(token);
-        token = lexer.read_token();
-    }
-    token_vec
+    lexer.position = start;
+    lexer.read_all_tokens()
+}
+
+// token と一緒に, 読み飛ばした comment を出現順に返す
+pub fn lex_with_comments(input: &str) -> Result<(Vec<SpannedToken>, Vec<Comment>), LexError> {
+    let mut lexer = Lexer::new(input);
+    let token_vec = lexer.read_all_tokens()?;
+    Ok((token_vec, lexer.comments))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn int(value: i64) -> Token {
+        Token::Int(BigInt::from_i64(value))
+    }
+
     #[test]
     fn read_char_test() {
         let input = "abc";
-        let mut lexer = Lexer::new(&input);
+        let mut lexer = Lexer::new(input);
 
         assert_eq!(lexer.read_char().unwrap_or(' '), 'a');
         assert_eq!(lexer.read_char().unwrap_or(' '), 'b');
@@ -159,7 +521,7 @@ mod test {
     #[test]
     fn peek_char_test() {
         let input = "abc";
-        let mut lexer = Lexer::new(&input);
+        let mut lexer = Lexer::new(input);
 
         assert_eq!(lexer.read_char().unwrap_or(' '), 'a');
         assert_eq!(lexer.peek_char().unwrap_or(' '), 'b');
@@ -173,7 +535,7 @@ mod test {
     #[test]
     fn skip_whitespace_test() {
         let input = "     a b  c ";
-        let mut lexer = Lexer::new(&input);
+        let mut lexer = Lexer::new(input);
 
         lexer.skip_whitespace();
         assert_eq!(lexer.read_char().unwrap_or(' '), 'a');
@@ -195,7 +557,7 @@ mod test {
         };
 
         let result = add(five, ten);
-        !-/*5;
+        !-/ *5;
         5 < 10 > 5;
         
         if (5 < 10) {
@@ -211,12 +573,12 @@ mod test {
             Token::Let,
             Token::Ident("five".chars().collect()),
             Token::Assign,
-            Token::Int("5".chars().collect()),
+            int(5),
             Token::SemiColon,
             Token::Let,
             Token::Ident("ten".chars().collect()),
             Token::Assign,
-            Token::Int("10".chars().collect()),
+            int(10),
             Token::SemiColon,
             Token::Let,
             Token::Ident("add".chars().collect()),
@@ -248,19 +610,19 @@ mod test {
             Token::Minus,
             Token::Slash,
             Token::Asterisk,
-            Token::Int("5".chars().collect()),
+            int(5),
             Token::SemiColon,
-            Token::Int("5".chars().collect()),
+            int(5),
             Token::Lt,
-            Token::Int("10".chars().collect()),
+            int(10),
             Token::Gt,
-            Token::Int("5".chars().collect()),
+            int(5),
             Token::SemiColon,
             Token::If,
             Token::LParen,
-            Token::Int("5".chars().collect()),
+            int(5),
             Token::Lt,
-            Token::Int("10".chars().collect()),
+            int(10),
             Token::RParen,
             Token::LBrace,
             Token::Return,
@@ -273,24 +635,395 @@ mod test {
             Token::False,
             Token::SemiColon,
             Token::RBrace,
-            Token::Int("10".chars().collect()),
+            int(10),
             Token::Eq,
-            Token::Int("10".chars().collect()),
+            int(10),
             Token::SemiColon,
-            Token::Int("10".chars().collect()),
+            int(10),
             Token::NotEq,
-            Token::Int("9".chars().collect()),
+            int(9),
             Token::SemiColon,
             Token::Eof,
         ];
 
-        let mut lexer = Lexer::new(&input);
+        let mut lexer = Lexer::new(input);
 
         for expect_token in tests.iter() {
-            let actual_token = lexer.read_token();
+            let actual_token = lexer.read_token().unwrap();
             println!("{:?}", expect_token);
 
             assert_eq!(actual_token, *expect_token);
         }
     }
+
+    #[test]
+    fn operator_test() {
+        let input = "10 <= 9 >= 8 % 3;
+        true && false || true;
+        x += 1 -= 2;";
+
+        let tests = vec![
+            int(10),
+            Token::LtEq,
+            int(9),
+            Token::GtEq,
+            int(8),
+            Token::Percent,
+            int(3),
+            Token::SemiColon,
+            Token::True,
+            Token::And,
+            Token::False,
+            Token::Or,
+            Token::True,
+            Token::SemiColon,
+            Token::Ident(vec!['x']),
+            Token::PlusAssign,
+            int(1),
+            Token::MinusAssign,
+            int(2),
+            Token::SemiColon,
+            Token::Eof,
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for expect_token in tests.iter() {
+            let actual_token = lexer.read_token().unwrap();
+            assert_eq!(actual_token, *expect_token);
+        }
+    }
+
+    #[test]
+    fn position_test() {
+        let input = "let x = 5;\n  x + 10;";
+        let mut lexer = Lexer::new(input);
+
+        lexer.read_char();
+        assert_eq!(
+            lexer.position,
+            Position {
+                offset: 1,
+                line: 1,
+                column: 2
+            }
+        );
+
+        let spans: Vec<(u32, u32, u32, u32)> = lex(input)
+            .unwrap()
+            .iter()
+            .map(|t| {
+                (
+                    t.span.start.line,
+                    t.span.start.column,
+                    t.span.end.line,
+                    t.span.end.column,
+                )
+            })
+            .collect();
+        let expect_spans = vec![
+            (1, 1, 1, 4),
+            (1, 5, 1, 6),
+            (1, 7, 1, 8),
+            (1, 9, 1, 10),
+            (1, 10, 1, 11),
+            (2, 3, 2, 4),
+            (2, 5, 2, 6),
+            (2, 7, 2, 9),
+            (2, 9, 2, 10),
+        ];
+        assert_eq!(spans, expect_spans);
+
+        let mut lexer = Lexer::new("é");
+        lexer.read_char();
+        assert_eq!(
+            lexer.position,
+            Position {
+                offset: 2,
+                line: 1,
+                column: 2
+            }
+        );
+    }
+
+    #[test]
+    fn lex_from_test() {
+        let start = Position {
+            offset: 20,
+            line: 3,
+            column: 1,
+        };
+        let tokens = lex_from("let\n x", start).unwrap();
+        assert_eq!(tokens[0].span.start, start);
+        assert_eq!(
+            tokens[1].span.start,
+            Position {
+                offset: 25,
+                line: 4,
+                column: 2
+            }
+        );
+    }
+
+    #[test]
+    fn string_test() {
+        let tests = vec![
+            (r#""foobar""#, "foobar"),
+            (r#""foo bar""#, "foo bar"),
+            (r#""""#, ""),
+            (r#""a\nb\tc""#, "a\nb\tc"),
+            (r#""say \"hi\" \\o/""#, "say \"hi\" \\o/"),
+            (r#""\u{41}\u{3042}\u{1F600}""#, "Aあ😀"),
+            ("\"multi\nline\"", "multi\nline"),
+        ];
+        for (input, expect) in tests {
+            let tokens = lex(input).unwrap();
+            assert_eq!(tokens.len(), 1);
+            assert_eq!(tokens[0].token, Token::Str(expect.chars().collect()));
+        }
+
+        let tokens = lex(r#"let s = "a" + "b";"#).unwrap();
+        assert_eq!(tokens[3].token, Token::Str(vec!['a']));
+        assert_eq!(tokens[3].span.start.column, 9);
+        assert_eq!(tokens[3].span.end.column, 12);
+    }
+
+    #[test]
+    fn string_error_test() {
+        let tests = vec![
+            (r#"let s = "abc"#, LexErrorKind::UnterminatedString, (1, 9)),
+            (r#""abc\"#, LexErrorKind::UnterminatedString, (1, 5)),
+            (r#""a\qb""#, LexErrorKind::UnknownEscape('q'), (1, 3)),
+            (
+                r#""\u{110000}""#,
+                LexErrorKind::InvalidUnicodeEscape("110000".to_string()),
+                (1, 2),
+            ),
+            (
+                r#""\u{zz}""#,
+                LexErrorKind::InvalidUnicodeEscape("zz".to_string()),
+                (1, 2),
+            ),
+            (
+                r#""\u41""#,
+                LexErrorKind::InvalidUnicodeEscape("".to_string()),
+                (1, 2),
+            ),
+        ];
+        for (input, kind, (line, column)) in tests {
+            let error = lex(input).err().unwrap();
+            assert_eq!(error.kind, kind);
+            assert_eq!(
+                (error.span.start.line, error.span.start.column),
+                (line, column)
+            );
+        }
+    }
+
+    #[test]
+    fn number_test() {
+        let tests = vec![
+            ("5", int(5)),
+            ("3.14", Token::Float("3.14".chars().collect())),
+            (".5", Token::Float(".5".chars().collect())),
+            ("1e-9", Token::Float("1e-9".chars().collect())),
+            ("2.5E+10", Token::Float("2.5E+10".chars().collect())),
+            ("6e3", Token::Float("6e3".chars().collect())),
+            ("0xFF", int(255)),
+            ("0xdead_beef", int(0xdead_beef)),
+            ("0o755", int(0o755)),
+            ("0b1010", int(10)),
+            ("0b_1111_0000", int(0xf0)),
+            ("1_000_000", int(1_000_000)),
+            ("007", int(7)),
+            ("1_000.5", Token::Float("1000.5".chars().collect())),
+            (
+                "0x1_0000_0000_0000_0000",
+                Token::Int(BigInt::parse("18446744073709551616").unwrap()),
+            ),
+        ];
+        for (input, expect) in tests {
+            let tokens = lex(input).unwrap();
+            assert_eq!(tokens.len(), 1, "{}", input);
+            assert_eq!(tokens[0].token, expect);
+        }
+
+        // `.` の前に空白があれば `.5` は別の token
+        let tokens: Vec<Token> = lex("[1] .5")
+            .unwrap()
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::LBracket,
+                int(1),
+                Token::RBracket,
+                Token::Float(".5".chars().collect())
+            ]
+        );
+    }
+
+    #[test]
+    fn number_error_test() {
+        let tests = vec![
+            (
+                "1.5.2",
+                LexErrorKind::MalformedNumber("1.5.2".to_string()),
+                1,
+                6,
+            ),
+            ("1.", LexErrorKind::MalformedNumber("1.".to_string()), 1, 3),
+            (
+                "1..5",
+                LexErrorKind::MalformedNumber("1..5".to_string()),
+                1,
+                5,
+            ),
+            (
+                "1e5.3",
+                LexErrorKind::MalformedNumber("1e5.3".to_string()),
+                1,
+                6,
+            ),
+            (
+                "[1, 2].0",
+                LexErrorKind::MalformedNumber(".0".to_string()),
+                7,
+                9,
+            ),
+            ("x.5", LexErrorKind::MalformedNumber(".5".to_string()), 2, 4),
+            ("1e", LexErrorKind::MalformedNumber("1e".to_string()), 1, 3),
+            (
+                "1e+ 2",
+                LexErrorKind::MalformedNumber("1e".to_string()),
+                1,
+                3,
+            ),
+            (
+                "2.5Ex",
+                LexErrorKind::MalformedNumber("2.5Ex".to_string()),
+                1,
+                6,
+            ),
+            (
+                "1e400",
+                LexErrorKind::FloatOutOfRange("1e400".to_string()),
+                1,
+                6,
+            ),
+            (
+                "1_e400",
+                LexErrorKind::FloatOutOfRange("1_e400".to_string()),
+                1,
+                7,
+            ),
+            ("0x", LexErrorKind::MissingDigits { radix: 16 }, 1, 3),
+            ("0o_;", LexErrorKind::MissingDigits { radix: 8 }, 1, 4),
+            (
+                "0b102",
+                LexErrorKind::InvalidDigit {
+                    digit: '2',
+                    radix: 2,
+                },
+                5,
+                6,
+            ),
+            (
+                "0o78",
+                LexErrorKind::InvalidDigit {
+                    digit: '8',
+                    radix: 8,
+                },
+                4,
+                5,
+            ),
+            (
+                "0xFG",
+                LexErrorKind::InvalidDigit {
+                    digit: 'G',
+                    radix: 16,
+                },
+                4,
+                5,
+            ),
+        ];
+        for (input, kind, start, end) in tests {
+            let error = lex(input).err().unwrap();
+            assert_eq!(error.kind, kind, "{}", input);
+            assert_eq!(error.span.start.column, start, "{}", input);
+            assert_eq!(error.span.end.column, end, "{}", input);
+        }
+
+        assert_eq!(
+            lex("0b102").err().unwrap().to_string(),
+            "1:5: invalid digit '2' in binary literal"
+        );
+        assert_eq!(
+            lex("0x").err().unwrap().to_string(),
+            "1:1: missing digits in hexadecimal literal"
+        );
+    }
+
+    #[test]
+    fn comment_test() {
+        let input = "// header\nlet x = 1; // trailing\n/* block /* nested */ still */ x / 2 /**/";
+        let (tokens, comments) = lex_with_comments(input).unwrap();
+        let tokens: Vec<Token> = tokens.into_iter().map(|t| t.token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Let,
+                Token::Ident(vec!['x']),
+                Token::Assign,
+                int(1),
+                Token::SemiColon,
+                Token::Ident(vec!['x']),
+                Token::Slash,
+                int(2),
+            ]
+        );
+
+        let texts: Vec<&str> = comments.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "// header",
+                "// trailing",
+                "/* block /* nested */ still */",
+                "/**/"
+            ]
+        );
+        assert_eq!(comments[1].span.start.line, 2);
+        assert_eq!(comments[1].span.start.column, 12);
+        assert_eq!(comments[1].span.end.column, 23);
+
+        // comment だけの入力や, 最終行の改行のない comment
+        assert_eq!(lex("// only").unwrap(), vec![]);
+        assert_eq!(lex("1 // 2").unwrap().len(), 1);
+        assert_eq!(lex("1 /* 2 */ + 3").unwrap().len(), 3);
+
+        let error = lex("let x = 1;\n/* a /* b */").err().unwrap();
+        assert_eq!(error.kind, LexErrorKind::UnterminatedComment);
+        assert_eq!(error.to_string(), "2:1: unterminated block comment");
+    }
+
+    #[test]
+    fn lex_error_test() {
+        assert_eq!(
+            lex("a & b").err().unwrap().kind,
+            LexErrorKind::IllegalCharacter('&')
+        );
+        assert_eq!(
+            lex("a | b").err().unwrap().kind,
+            LexErrorKind::IllegalCharacter('|')
+        );
+
+        let error = lex("let a = 1;\nlet b = a @ 2;").err().unwrap();
+        assert_eq!(error.kind, LexErrorKind::IllegalCharacter('@'));
+        assert_eq!(error.span.start.line, 2);
+        assert_eq!(error.span.start.column, 11);
+        assert_eq!(error.to_string(), "2:11: illegal character '@'");
+    }
 }
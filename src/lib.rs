@@ -1,7 +1,14 @@
 pub mod ast;
+pub mod bigint;
+pub mod builtin;
+pub mod diagnostic;
 pub mod env;
+pub mod error;
 pub mod evaluator;
+pub mod interpreter;
 pub mod lexer;
 pub mod object;
 pub mod parser;
 pub mod token;
+
+pub use interpreter::Interpreter;
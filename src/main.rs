@@ -1,3 +1,102 @@
+mod repl;
+mod runner;
+
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::process;
+use std::thread;
+
+use monkey_by_rust::diagnostic;
+use monkey_by_rust::object::Object;
+use monkey_by_rust::Interpreter;
+
+const USAGE: &str = "usage: monkey               start the REPL
+       monkey run <file>    run a Monkey script
+       monkey -e <code>     evaluate code given on the command line";
+
+// 評価器は関数呼び出しごとに Rust の stack を使う. 呼び出しの上限まで debug build でも
+// 再帰できるよう大きな stack の thread で評価し, main などの frame の分を残して評価器に使わせる
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+const STACK_BUDGET: usize = STACK_SIZE - 4 * 1024 * 1024;
+
 fn main() {
-    println!("Hello, world!");
+    let status = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .and_then(|handle| {
+            handle
+                .join()
+                .map_err(|_| io::Error::other("interpreter panicked"))
+        })
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            1
+        });
+    process::exit(status);
+}
+
+// この thread の stack に合わせた Interpreter
+fn new_interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stack_budget(STACK_BUDGET);
+    interpreter
+}
+
+fn run() -> i32 {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] => start_repl(),
+        ["run", path] => match fs::read_to_string(path) {
+            Ok(source) => report(&source, path),
+            Err(error) => {
+                eprintln!("cannot read {}: {}", path, error);
+                1
+            }
+        },
+        ["-e", source] => report(source, "<command line>"),
+        ["-h"] | ["--help"] => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+fn start_repl() -> i32 {
+    println!("Monkey REPL (type :help for commands)");
+    let stdin = io::stdin();
+    match repl::Repl::new(use_color(&io::stdout())).run(stdin.lock(), io::stdout()) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
+    }
+}
+
+// 最終的な値を stdout に, エラーを stderr に出力して終了ステータスを返す
+fn report(source: &str, name: &str) -> i32 {
+    match runner::run_source(source) {
+        Ok(Object::Null) => 0,
+        Ok(object) => {
+            println!("{}", object.literal());
+            0
+        }
+        Err(error) => {
+            let color = use_color(&io::stderr());
+            eprint!("{}", diagnostic::render_error(&error, source, name, color));
+            1
+        }
+    }
+}
+
+// 端末に出力するときだけ色をつける (NO_COLOR が設定されていれば常に無効)
+fn use_color(stream: &impl IsTerminal) -> bool {
+    stream.is_terminal() && env::var_os("NO_COLOR").is_none()
 }
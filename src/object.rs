@@ -1,23 +1,66 @@
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
-use crate::{ast::StatementNode, env::Enviroment};
+use crate::{
+    ast::StatementNode, bigint::BigInt, env::Enviroment, error::RuntimeError, token::Span,
+};
+
+pub type BuiltinFunction = fn(&[Object]) -> Result<Object, RuntimeError>;
+
+// Interpreter::register_fn で登録される, 埋め込み先の application が提供する関数
+pub type HostFunction = Rc<dyn Fn(&[Object]) -> Result<Object, RuntimeError>>;
 
 #[derive(Clone)]
 pub enum Object {
     Integer {
-        value: i32,
+        value: i64,
+    },
+    // i64 に収まらない整数. i64 に収まる値は常に Integer で表す
+    BigInteger {
+        value: BigInt,
+    },
+    Float {
+        value: f64,
     },
     Boolean {
         value: bool,
     },
+    String {
+        value: String,
+    },
+    Array {
+        elements: Vec<Object>,
+    },
+    Hash {
+        pairs: BTreeMap<HashKey, Object>,
+    },
+    // range builtin が返す start 以上 end 未満の整数の列. 要素は反復するときに作る
+    Range {
+        start: i64,
+        end: i64,
+    },
     ReturnValue {
         value: Box<Object>,
     },
+    // break と continue を, ReturnValue と同じように loop まで伝える
+    Break,
+    Continue,
     FunctionObject {
         parameters: Vec<String>,
         body: Box<StatementNode>,
+        // 定義したときの環境をそのまま共有する. 代入はそこから作った closure すべてに見える
         env: Option<Rc<RefCell<Enviroment>>>,
+        span: Span,
+    },
+    Builtin {
+        name: &'static str,
+        function: BuiltinFunction,
+    },
+    HostFunction {
+        name: String,
+        arity: usize,
+        function: HostFunction,
     },
     Null,
 }
@@ -26,6 +69,9 @@ impl Object {
     pub fn literal(&self) -> String {
         match &self {
             Object::Integer { value } => value.to_string(),
+            Object::BigInteger { value } => value.to_string(),
+            // Debug 表示は読み戻すと同じ値になる最短の表記で, 整数値でも `1.0` のように小数点をつける
+            Object::Float { value } => format!("{:?}", value),
             Object::Boolean { value } => {
                 if *value {
                     "true".to_string()
@@ -33,16 +79,185 @@ impl Object {
                     "false".to_string()
                 }
             }
+            Object::String { value } => value.clone(),
+            Object::Array { elements } => {
+                let elements: Vec<String> = elements.iter().map(Object::element_literal).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Object::Hash { pairs } => {
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| {
+                        format!(
+                            "{}: {}",
+                            key.to_object().element_literal(),
+                            value.element_literal()
+                        )
+                    })
+                    .collect();
+                format!("{{{}}}", pairs.join(", "))
+            }
+            Object::Range { start, end } => format!("range({}, {})", start, end),
             Object::ReturnValue { value } => value.literal(),
+            Object::Break => "break".to_string(),
+            Object::Continue => "continue".to_string(),
             &Object::FunctionObject {
-                parameters,
-                body,
-                env: _,
+                parameters, body, ..
             } => {
                 let parameters = parameters.join(", ");
                 format!("fn({}) {}", parameters, body.literal())
             }
+            Object::Builtin { name, .. } => format!("builtin function {}", name),
+            Object::HostFunction { name, .. } => format!("builtin function {}", name),
             Object::Null => "null".to_string(),
         }
     }
+
+    // 配列の要素として表示するときは, 文字列を引用符で囲んで区別する
+    fn element_literal(&self) -> String {
+        match self {
+            Object::String { value } => format!("{:?}", value),
+            _ => self.literal(),
+        }
+    }
+
+    // for-in で反復できれば, 要素を (key, value) の組で返す iterator を作る
+    // 配列, 文字列, range の key は 0 からの index, hash の key は hash の key
+    pub fn iter(&self) -> Option<ObjectIter> {
+        let iter = match self {
+            Object::Array { elements } => ObjectIter::Elements {
+                elements: elements.clone().into_iter(),
+                index: 0,
+            },
+            Object::String { value } => ObjectIter::Chars {
+                chars: value.chars().collect::<Vec<char>>().into_iter(),
+                index: 0,
+            },
+            Object::Hash { pairs } => ObjectIter::Pairs(pairs.clone().into_iter()),
+            Object::Range { start, end } => ObjectIter::Range {
+                next: *start,
+                end: *end,
+                index: 0,
+            },
+            _ => return None,
+        };
+        Some(iter)
+    }
+
+    // 整数を i64 に収まれば Integer, 収まらなければ BigInteger にする
+    pub fn from_big_int(value: BigInt) -> Object {
+        match value.to_i64() {
+            Some(value) => Object::Integer { value },
+            None => Object::BigInteger { value },
+        }
+    }
+
+    // エラーメッセージで使う型の名前
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Integer { .. } | Object::BigInteger { .. } => "integer",
+            Object::Float { .. } => "float",
+            Object::Boolean { .. } => "boolean",
+            Object::String { .. } => "string",
+            Object::Array { .. } => "array",
+            Object::Hash { .. } => "hash",
+            Object::Range { .. } => "range",
+            Object::ReturnValue { value } => value.type_name(),
+            Object::Break | Object::Continue => "null",
+            Object::FunctionObject { .. } => "function",
+            Object::Builtin { .. } | Object::HostFunction { .. } => "builtin",
+            Object::Null => "null",
+        }
+    }
+}
+
+// Object::iter が返す iterator. 反復を始めた時点の要素を返し, 途中で元の値が変わっても影響しない
+pub enum ObjectIter {
+    Elements {
+        elements: std::vec::IntoIter<Object>,
+        index: i64,
+    },
+    Chars {
+        chars: std::vec::IntoIter<char>,
+        index: i64,
+    },
+    Pairs(std::collections::btree_map::IntoIter<HashKey, Object>),
+    Range {
+        next: i64,
+        end: i64,
+        index: i64,
+    },
+}
+
+impl Iterator for ObjectIter {
+    type Item = (Object, Object);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ObjectIter::Elements { elements, index } => {
+                let element = elements.next()?;
+                *index += 1;
+                Some((Object::Integer { value: *index - 1 }, element))
+            }
+            ObjectIter::Chars { chars, index } => {
+                let c = chars.next()?;
+                *index += 1;
+                Some((
+                    Object::Integer { value: *index - 1 },
+                    Object::String {
+                        value: c.to_string(),
+                    },
+                ))
+            }
+            ObjectIter::Pairs(pairs) => {
+                let (key, value) = pairs.next()?;
+                Some((key.to_object(), value))
+            }
+            ObjectIter::Range { next, end, index } => {
+                if *next >= *end {
+                    return None;
+                }
+                *next += 1;
+                *index += 1;
+                Some((
+                    Object::Integer { value: *index - 1 },
+                    Object::Integer { value: *next - 1 },
+                ))
+            }
+        }
+    }
+}
+
+// hash の key に使える値. 関数や配列などは key にできない
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HashKey {
+    Integer(i64),
+    BigInteger(BigInt),
+    Boolean(bool),
+    String(String),
+}
+
+impl HashKey {
+    pub fn from_object(object: &Object) -> Option<HashKey> {
+        match object {
+            Object::Integer { value } => Some(HashKey::Integer(*value)),
+            Object::BigInteger { value } => Some(HashKey::BigInteger(value.clone())),
+            Object::Boolean { value } => Some(HashKey::Boolean(*value)),
+            Object::String { value } => Some(HashKey::String(value.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            HashKey::Integer(value) => Object::Integer { value: *value },
+            HashKey::BigInteger(value) => Object::BigInteger {
+                value: value.clone(),
+            },
+            HashKey::Boolean(value) => Object::Boolean { value: *value },
+            HashKey::String(value) => Object::String {
+                value: value.clone(),
+            },
+        }
+    }
 }
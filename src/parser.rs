@@ -1,71 +1,126 @@
+use std::collections::HashMap;
+
 use crate::ast::*;
-use crate::token::Token;
+use crate::error::{ParseError, ParseErrorKind};
+use crate::token::{Span, SpannedToken, Token};
+
+// 入れ子が深すぎる入力で stack overflow しないための上限
+const MAX_NESTING_DEPTH: usize = 256;
 
 struct Parser {
-    token_vec: Vec<Token>,
+    token_vec: Vec<SpannedToken>,
     next_read_index: usize,
+    depth: usize,
+    // 読んでいる while の入れ子の深さ. 関数の中に入ると 0 から数えなおす
+    loop_depth: usize,
+    // 値として使う if の block を読んでいる間は, その外側の loop を break できない
+    loop_floor: usize,
+    // 最後に読んだ break, continue の位置
+    last_loop_control: Option<(&'static str, Span)>,
+    // 宣言された変数が変更できるかを scope ごとに記録する. 関数や loop の body で 1 段積む
+    scopes: Vec<HashMap<String, bool>>,
+    errors: Vec<ParseError>,
 }
 
 impl Parser {
-    fn new(token_vec: Vec<Token>) -> Self {
+    fn new(token_vec: Vec<SpannedToken>) -> Self {
         Parser {
             token_vec,
             next_read_index: 0,
+            depth: 0,
+            loop_depth: 0,
+            loop_floor: 0,
+            last_loop_control: None,
+            scopes: vec![HashMap::new()],
+            errors: Vec::new(),
         }
     }
 
-    fn read_token(&mut self) -> Option<Token> {
-        let return_value: Option<Token>;
+    fn read_token(&mut self) -> Token {
+        let return_value: Token;
         if self.next_read_index < self.token_vec.len() {
-            return_value = Some(self.token_vec[self.next_read_index].clone());
+            return_value = self.token_vec[self.next_read_index].token.clone();
             self.next_read_index += 1;
         } else {
-            return_value = Some(Token::Eof);
+            return_value = Token::Eof;
         };
         return_value
     }
 
-    fn peek_token(&self) -> Option<Token> {
+    fn peek_token(&self) -> Token {
         if self.next_read_index < self.token_vec.len() {
-            Some(self.token_vec[self.next_read_index].clone())
+            self.token_vec[self.next_read_index].token.clone()
         } else {
-            Some(Token::Eof)
+            Token::Eof
         }
     }
 
-    fn expect_token(&self, expect_token: Token) -> Result<(), String> {
-        let token_opt = self.peek_token();
-        match token_opt {
-            Some(token) => {
-                if token == expect_token {
-                    Ok(())
-                } else {
-                    Err(format!("expect: {:?}, actual: {:?}", expect_token, token)
-                        .chars()
-                        .collect())
-                }
-            }
-            None => Err(format!("expect: {:?}, actual: None", expect_token)
-                .chars()
-                .collect()),
+    // 次に読む token の Span. 末尾に達していれば入力の終端を指す
+    fn peek_span(&self) -> Span {
+        match self.token_vec.get(self.next_read_index) {
+            Some(spanned_token) => spanned_token.span,
+            None => self.eof_span(),
         }
     }
 
-    fn parse_statement(&mut self) -> Result<StatementNode, String> {
-        if let Some(token) = self.peek_token() {
-            match token {
-                Token::Let => self.parse_let_statement(),
-                Token::Return => self.parse_return_statement(),
-                _ => self.parse_expression_statement(),
-            }
+    // 最後に読んだ token の Span
+    fn last_span(&self) -> Span {
+        if self.next_read_index == 0 {
+            return self.peek_span();
+        }
+        match self.token_vec.get(self.next_read_index - 1) {
+            Some(spanned_token) => spanned_token.span,
+            None => self.eof_span(),
+        }
+    }
+
+    fn eof_span(&self) -> Span {
+        match self.token_vec.last() {
+            Some(spanned_token) => Span::new(spanned_token.span.end, spanned_token.span.end),
+            None => Span::default(),
+        }
+    }
+
+    fn expect_token(&self, expect_token: Token) -> Result<(), ParseError> {
+        if self.peek_token() == expect_token {
+            Ok(())
         } else {
-            Err("".to_string())
+            Err(self.unexpected_token(&format!("{:?}", expect_token)))
         }
     }
 
-    fn parse_let_statement(&mut self) -> Result<StatementNode, String> {
-        // Token::Let skip
-        self.expect_token(Token::Let)?;
+    // 次に読む token が期待していたものではなかった
+    fn unexpected_token(&self, expected: &str) -> ParseError {
+        ParseError::new(
+            ParseErrorKind::UnexpectedToken {
+                expected: expected.to_string(),
+                actual: self.peek_token(),
+            },
+            self.peek_span(),
+        )
+    }
+
+    fn parse_statement(&mut self) -> Result<StatementNode, ParseError> {
+        match self.peek_token() {
+            Token::Let | Token::Const => self.parse_let_statement(),
+            Token::Return => self.parse_return_statement(),
+            Token::While => self.parse_while_statement(),
+            Token::For => self.parse_for_statement(),
+            Token::Break | Token::Continue => self.parse_loop_control_statement(),
+            _ => self.parse_expression_statement(),
+        }
+    }
+
+    // let と const
+    fn parse_let_statement(&mut self) -> Result<StatementNode, ParseError> {
+        let start = self.peek_span();
+
+        // Token::Let または Token::Const skip
+        let mutable = match self.peek_token() {
+            Token::Let => true,
+            Token::Const => false,
+            _ => return Err(self.unexpected_token("let or const")),
+        };
         self.read_token();
 
         // Identifier 読み込み
@@ -76,24 +131,42 @@ impl Parser {
         self.read_token();
 
         // ExpressionNode 読み込み
-        let value = self.parse_expression(BindingPower::LOWEST)?;
+        let value = self.parse_expression(BindingPower::Lowest)?;
 
         // Token::SemiColon が存在するならば skip
         if self.expect_token(Token::SemiColon).is_ok() {
             self.read_token();
         }
 
+        // 値の式を読んだ後で宣言する. `let x = x + 1` の右辺は外側の x を指す
+        self.declare(&identifier, mutable);
+
         // return
-        Ok(StatementNode::LetStatement { identifier, value })
+        let span = start.to(self.last_span());
+        Ok(if mutable {
+            StatementNode::LetStatement {
+                identifier,
+                value,
+                span,
+            }
+        } else {
+            StatementNode::ConstStatement {
+                identifier,
+                value,
+                span,
+            }
+        })
     }
 
-    fn parse_return_statement(&mut self) -> Result<StatementNode, String> {
+    fn parse_return_statement(&mut self) -> Result<StatementNode, ParseError> {
+        let start = self.peek_span();
+
         // Token::Return skip
         self.expect_token(Token::Return)?;
         self.read_token();
 
         // ExpressionNode 読み込み
-        let return_value = self.parse_expression(BindingPower::LOWEST)?;
+        let return_value = self.parse_expression(BindingPower::Lowest)?;
 
         // Token::SemiColon が存在するならば skip
         if self.expect_token(Token::SemiColon).is_ok() {
@@ -101,12 +174,150 @@ impl Parser {
         }
 
         // return
-        Ok(StatementNode::ReturnStatement { return_value })
+        Ok(StatementNode::ReturnStatement {
+            return_value,
+            span: start.to(self.last_span()),
+        })
+    }
+
+    fn parse_while_statement(&mut self) -> Result<StatementNode, ParseError> {
+        let start = self.peek_span();
+
+        // Token::While skip
+        self.expect_token(Token::While)?;
+        self.read_token();
+
+        // Token::LParen skip
+        self.expect_token(Token::LParen)?;
+        self.read_token();
+
+        // 条件式の読み込み
+        let condition = self.parse_expression(BindingPower::Lowest)?;
+
+        // Token::RParen skip
+        self.expect_token(Token::RParen)?;
+        self.read_token();
+
+        // body の読み込み
+        self.loop_depth += 1;
+        self.scopes.push(HashMap::new());
+        let body = self.parse_block_statement();
+        self.scopes.pop();
+        self.loop_depth -= 1;
+        let body = body?;
+
+        // Token::SemiColon が存在するならば skip. span には含めない
+        let span = start.to(body.span());
+        if self.expect_token(Token::SemiColon).is_ok() {
+            self.read_token();
+        }
+
+        Ok(StatementNode::WhileStatement {
+            condition,
+            body,
+            span,
+        })
+    }
+
+    fn parse_for_statement(&mut self) -> Result<StatementNode, ParseError> {
+        let start = self.peek_span();
+
+        // Token::For skip
+        self.expect_token(Token::For)?;
+        self.read_token();
+
+        // Token::LParen skip
+        self.expect_token(Token::LParen)?;
+        self.read_token();
+
+        // 変数の読み込み. 2 つあれば key と value
+        let first = self.parse_identifier()?;
+        let (key, value) = if self.expect_token(Token::Comma).is_ok() {
+            // Token::Comma skip
+            self.read_token();
+            (Some(first), self.parse_identifier()?)
+        } else {
+            (None, first)
+        };
+
+        // Token::In skip
+        self.expect_token(Token::In)?;
+        self.read_token();
+
+        // 反復する式の読み込み
+        let iterable = self.parse_expression(BindingPower::Lowest)?;
+
+        // Token::RParen skip
+        self.expect_token(Token::RParen)?;
+        self.read_token();
+
+        // body の読み込み. 変数は反復ごとの scope に束縛される
+        self.loop_depth += 1;
+        self.scopes.push(HashMap::new());
+        for identifier in key.iter().chain(std::iter::once(&value)) {
+            self.declare(identifier, true);
+        }
+        let body = self.parse_block_statement();
+        self.scopes.pop();
+        self.loop_depth -= 1;
+        let body = body?;
+
+        // Token::SemiColon が存在するならば skip. span には含めない
+        let span = start.to(body.span());
+        if self.expect_token(Token::SemiColon).is_ok() {
+            self.read_token();
+        }
+
+        Ok(StatementNode::ForStatement {
+            key,
+            value,
+            iterable,
+            body,
+            span,
+        })
+    }
+
+    // break と continue
+    fn parse_loop_control_statement(&mut self) -> Result<StatementNode, ParseError> {
+        let start = self.peek_span();
+        let token = self.peek_token();
+        let keyword = match token {
+            Token::Break => "break",
+            Token::Continue => "continue",
+            _ => return Err(self.unexpected_token("break or continue")),
+        };
+        if self.loop_depth == 0 {
+            return Err(ParseError::new(ParseErrorKind::OutsideLoop(keyword), start));
+        }
+        if self.loop_depth <= self.loop_floor {
+            return Err(ParseError::new(
+                ParseErrorKind::LoopControlInExpression(keyword),
+                start,
+            ));
+        }
+        self.last_loop_control = Some((keyword, start));
+        self.read_token();
+
+        // Token::SemiColon が存在するならば skip
+        if self.expect_token(Token::SemiColon).is_ok() {
+            self.read_token();
+        }
+
+        Ok(match token {
+            Token::Break => StatementNode::BreakStatement { span: start },
+            _ => StatementNode::ContinueStatement { span: start },
+        })
     }
 
-    fn parse_expression_statement(&mut self) -> Result<StatementNode, String> {
+    fn parse_expression_statement(&mut self) -> Result<StatementNode, ParseError> {
+        let start = self.peek_span();
+
         // ExpressionNode 読み込み
-        let expression = self.parse_expression(BindingPower::LOWEST)?;
+        let expression = if self.peek_token() == Token::If {
+            self.parse_statement_if_expression()?
+        } else {
+            self.parse_expression(BindingPower::Lowest)?
+        };
 
         // Token::SemiColon が存在するならば skip
         if self.expect_token(Token::SemiColon).is_ok() {
@@ -114,85 +325,213 @@ impl Parser {
         }
 
         // return
-        Ok(StatementNode::ExpressionStatement { expression })
+        Ok(StatementNode::ExpressionStatement {
+            expression,
+            span: start.to(self.last_span()),
+        })
     }
 
-    fn parse_block_statement(&mut self) -> Result<Box<StatementNode>, String> {
+    // 文の先頭の if は値を捨てるので, 中の break, continue は外側の loop に届く.
+    // 後ろに演算子が続いて値として使われるなら, break, continue は書けない
+    fn parse_statement_if_expression(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let depth = self.depth;
+        let last_loop_control = self.last_loop_control;
+        let expression = self
+            .enter_nesting()
+            .and_then(|_| self.parse_if_expression(false));
+        self.depth = depth;
+        let expression = expression?;
+
+        if to_binding_power(&self.peek_token()) <= BindingPower::Lowest {
+            return Ok(expression);
+        }
+        match self.last_loop_control {
+            Some((keyword, span)) if self.last_loop_control != last_loop_control => Err(
+                ParseError::new(ParseErrorKind::LoopControlInExpression(keyword), span),
+            ),
+            _ => {
+                let expression = self
+                    .enter_nesting()
+                    .and_then(|_| self.parse_led_expressions(expression, BindingPower::Lowest));
+                self.depth = depth;
+                expression
+            }
+        }
+    }
+
+    // block の中の文は block を再帰で読むので, 式と同じく入れ子の深さに数える
+    fn parse_block_statement(&mut self) -> Result<Box<StatementNode>, ParseError> {
+        let depth = self.depth;
+        let block = self.parse_nested_block_statement();
+        self.depth = depth;
+        block
+    }
+
+    fn parse_nested_block_statement(&mut self) -> Result<Box<StatementNode>, ParseError> {
+        self.enter_nesting()?;
+        let start = self.peek_span();
+
         // Token::LBrace skip
         self.expect_token(Token::LBrace)?;
         self.read_token();
 
         let mut statements: Vec<StatementNode> = vec![];
 
-        while (!self.expect_token(Token::RBrace).is_ok())
-            && (!self.expect_token(Token::Eof).is_ok())
+        while (self.expect_token(Token::RBrace).is_err())
+            && (self.expect_token(Token::Eof).is_err())
         {
-            let statement = self.parse_statement()?;
-            statements.push(statement);
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    // エラーを記録して, ブロック内の次の文から再開する
+                    self.errors.push(error);
+                    self.synchronize();
+                }
+            }
         }
 
-        if self.expect_token(Token::Eof).is_ok() {
-            return Err("in parse_block_statement".to_string());
-        }
+        // Token::Eof ならばここでエラー
+        self.expect_token(Token::RBrace)
+            .map_err(|error| error.with_note(start, "block opened here"))?;
 
-        // Token::RBrace skip
+        // Token::RBrace skip. 後ろの Token::SemiColon は block を含む文が読む
         self.read_token();
+        let span = start.to(self.last_span());
 
-        // Token::SemiColon が存在するならば skip
-        if self.expect_token(Token::SemiColon).is_ok() {
-            self.read_token();
-        }
-
-        Ok(Box::new(StatementNode::BlockStatement { statements }))
+        Ok(Box::new(StatementNode::BlockStatement { statements, span }))
     }
 
     fn parse_expression(
         &mut self,
         right_binding_power: BindingPower,
-    ) -> Result<Box<ExpressionNode>, String> {
-        let mut left = self.parse_nud_expression()?;
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        let depth = self.depth;
+        let expression = self.parse_expression_with_power(right_binding_power);
+        self.depth = depth;
+        expression
+    }
 
-        let mut left_binding_power =
-            to_binding_power(&self.peek_token().ok_or("None token".to_string())?);
+    fn parse_expression_with_power(
+        &mut self,
+        right_binding_power: BindingPower,
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        self.enter_nesting()?;
+        let left = self.parse_nud_expression()?;
+        self.parse_led_expressions(left, right_binding_power)
+    }
+
+    fn parse_led_expressions(
+        &mut self,
+        mut left: Box<ExpressionNode>,
+        right_binding_power: BindingPower,
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        let mut left_binding_power = to_binding_power(&self.peek_token());
+        // led は loop で左に積むだけなので, 入れ子の深さには数えない
         while right_binding_power < left_binding_power {
             left = self.parse_led_expression(left)?;
-            left_binding_power =
-                to_binding_power(&self.peek_token().ok_or("None token".to_string())?);
+            left_binding_power = to_binding_power(&self.peek_token());
         }
         Ok(left)
     }
 
-    fn parse_nud_expression(&mut self) -> Result<Box<ExpressionNode>, String> {
-        let token = self.peek_token().ok_or("None token".to_string())?;
+    // panic-mode recovery. `;` を読み飛ばした直後か, 対応する `{` のない `}` の手前まで進める
+    fn synchronize(&mut self) {
+        let mut brace_depth = 0;
+        loop {
+            match self.peek_token() {
+                Token::Eof => return,
+                Token::SemiColon if brace_depth == 0 => {
+                    self.read_token();
+                    return;
+                }
+                Token::RBrace if brace_depth == 0 => return,
+                Token::LBrace => brace_depth += 1,
+                Token::RBrace => brace_depth -= 1,
+                _ => {}
+            }
+            self.read_token();
+        }
+    }
+
+    // 今の scope に変数を宣言する. 同じ scope の const は宣言しなおせない
+    fn declare(&mut self, identifier: &ExpressionNode, mutable: bool) {
+        let (name, span) = match identifier {
+            ExpressionNode::Identifier { literal, span } => {
+                (literal.iter().collect::<String>(), *span)
+            }
+            _ => return,
+        };
+        let scope = self.scopes.last_mut().expect("parser has no scope");
+        if scope.get(&name) == Some(&false) {
+            self.errors.push(ParseError::new(
+                ParseErrorKind::RedeclareConstant(name),
+                span,
+            ));
+            return;
+        }
+        scope.insert(name, mutable);
+    }
+
+    // 内側の scope から順に探して, 変数が変更できるか. この入力で宣言されていなければ None
+    fn lookup_mutable(&self, name: &str) -> Option<bool> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn enter_nesting(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            Err(ParseError::new(
+                ParseErrorKind::NestingTooDeep,
+                self.peek_span(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn parse_nud_expression(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let token = self.peek_token();
         // match
         let nud_expression: Box<ExpressionNode> = match token {
             Token::Ident(_) => self.parse_identifier()?,
             Token::Int(_) => self.parse_integer()?,
+            Token::Float(_) => self.parse_float()?,
+            Token::Str(_) => self.parse_string()?,
+            Token::LBracket => self.parse_array_literal()?,
+            // block は if, fn の後ろでしか読まないので, 式の位置の `{` は hash literal
+            Token::LBrace => self.parse_hash_literal()?,
             Token::True | Token::False => self.parse_boolean()?,
             Token::LParen => self.parse_grouped_expression()?,
             Token::Bang | Token::Minus => self.parse_prefix()?,
-            Token::If => self.parse_if_expression()?,
+            Token::If => self.parse_if_expression(true)?,
             Token::Function => self.parse_function_literal()?,
-            _ => panic!("in parse_nud_expression"),
+            _ => return Err(self.unexpected_token("expression")),
         };
         Ok(nud_expression)
     }
 
-    fn parse_grouped_expression(&mut self) -> Result<Box<ExpressionNode>, String> {
+    fn parse_grouped_expression(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+
         // Token::LParen skip
         self.expect_token(Token::LParen)?;
         self.read_token();
 
-        let expression = self.parse_expression(BindingPower::LOWEST);
+        let expression = self.parse_expression(BindingPower::Lowest)?;
 
         // Token::RParen skip
-        self.expect_token(Token::RParen)?;
+        self.expect_token(Token::RParen)
+            .map_err(|error| error.with_note(start, "parenthesis opened here"))?;
         self.read_token();
 
-        expression
+        Ok(expression)
     }
 
-    fn parse_function_literal(&mut self) -> Result<Box<ExpressionNode>, String> {
+    fn parse_function_literal(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
         // Token::Function skip
         self.expect_token(Token::Function)?;
         self.read_token();
@@ -203,13 +542,16 @@ impl Parser {
 
         // parameters の読み込み
         let mut parameters = Vec::new();
-        while !self.expect_token(Token::RParen).is_ok() {
+        while self.expect_token(Token::RParen).is_err() {
             let parameter = self.parse_identifier()?;
             parameters.push(parameter);
 
-            // Token::Comma が存在するならば skip
+            // 次の引数との間には Token::Comma が必要
             if self.expect_token(Token::Comma).is_ok() {
                 self.read_token();
+            } else {
+                self.expect_token(Token::RParen)
+                    .map_err(|_| self.unexpected_token("Comma or RParen"))?;
             }
         }
 
@@ -217,29 +559,46 @@ impl Parser {
         self.expect_token(Token::RParen)?;
         self.read_token();
 
-        // body の読み込み
-        let body = self.parse_block_statement()?;
+        // body の読み込み. 関数の外側の loop は body から break できない
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let loop_floor = std::mem::replace(&mut self.loop_floor, 0);
+        self.scopes.push(HashMap::new());
+        for parameter in parameters.iter() {
+            self.declare(parameter, true);
+        }
+        let body = self.parse_block_statement();
+        self.scopes.pop();
+        self.loop_depth = loop_depth;
+        self.loop_floor = loop_floor;
+        let body = body?;
 
+        let span = start.to(body.span());
         Ok(Box::new(ExpressionNode::FunctionLiteral {
             parameters,
             body,
+            span,
         }))
     }
 
-    fn parse_prefix(&mut self) -> Result<Box<ExpressionNode>, String> {
-        let operator_type = match self.read_token().ok_or("None token".to_string())? {
+    fn parse_prefix(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+        let operator_type = match self.peek_token() {
             Token::Bang => PrefixOperatorType::Bang,
             Token::Minus => PrefixOperatorType::Minus,
-            _ => panic!("in parse_prefix"),
+            _ => return Err(self.unexpected_token("prefix operator")),
         };
-        let right = self.parse_expression(BindingPower::PREFIX)?;
+        self.read_token();
+        let right = self.parse_expression(BindingPower::Prefix)?;
         Ok(Box::new(ExpressionNode::PrefixOperator {
             operator_type,
             right,
+            span: start.to(self.last_span()),
         }))
     }
 
-    fn parse_if_expression(&mut self) -> Result<Box<ExpressionNode>, String> {
+    // as_value なら, block の中から外側の loop を break, continue できない
+    fn parse_if_expression(&mut self, as_value: bool) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
         // Token::If skip
         self.expect_token(Token::If)?;
         self.read_token();
@@ -248,168 +607,451 @@ impl Parser {
         self.expect_token(Token::LParen)?;
         self.read_token();
 
-        let condition = self.parse_expression(BindingPower::LOWEST)?;
+        let condition = self.parse_expression(BindingPower::Lowest)?;
 
         // Token::RParen skip
         self.expect_token(Token::RParen)?;
         self.read_token();
 
+        let loop_floor = self.loop_floor;
+        if as_value {
+            self.loop_floor = self.loop_depth;
+        }
+        let blocks = self.parse_if_blocks();
+        self.loop_floor = loop_floor;
+        let (consequence, alternative) = blocks?;
+
+        Ok(Box::new(ExpressionNode::IfExpression {
+            condition,
+            consequence,
+            alternative,
+            span: start.to(self.last_span()),
+        }))
+    }
+
+    // 分岐はどちらか片方しか実行されないので, それぞれ if の前の scope から読み, 後で合わせる
+    fn parse_if_blocks(
+        &mut self,
+    ) -> Result<(Box<StatementNode>, Option<Box<StatementNode>>), ParseError> {
+        let outer_scope = self.scopes.last().cloned().unwrap_or_default();
+
         // BlockStatement 読み込み
         let consequence = self.parse_block_statement()?;
+        let consequence_scope = std::mem::replace(
+            self.scopes.last_mut().expect("parser has no scope"),
+            outer_scope,
+        );
 
         // Token::Else が存在するなら, さらに読み込み
         let alternative = if self.expect_token(Token::Else).is_ok() {
             // Token::Else skip
             self.read_token();
-            Some(self.parse_block_statement()?)
+            Some(self.parse_block_statement())
         } else {
             None
         };
 
-        Ok(Box::new(ExpressionNode::IfExpression {
-            condition,
-            consequence,
-            alternative,
-        }))
+        // どちらの分岐でも const なものだけを const として残す
+        let scope = self.scopes.last_mut().expect("parser has no scope");
+        let alternative_scope = std::mem::take(scope);
+        for name in consequence_scope.keys().chain(alternative_scope.keys()) {
+            let mutable = consequence_scope.get(name) != Some(&false)
+                || alternative_scope.get(name) != Some(&false);
+            scope.insert(name.clone(), mutable);
+        }
+        Ok((consequence, alternative.transpose()?))
     }
 
     fn parse_led_expression(
         &mut self,
         left: Box<ExpressionNode>,
-    ) -> Result<Box<ExpressionNode>, String> {
-        let token = self.peek_token().ok_or("None token".to_string())?;
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        let token = self.peek_token();
 
         let led_expression: Box<ExpressionNode> = match token {
             Token::Plus
             | Token::Minus
             | Token::Asterisk
             | Token::Slash
+            | Token::Percent
             | Token::Eq
             | Token::NotEq
             | Token::Gt
-            | Token::Lt => self.parse_infix(left)?,
+            | Token::Lt
+            | Token::GtEq
+            | Token::LtEq => self.parse_infix(left)?,
+            Token::And | Token::Or => self.parse_logical(left)?,
+            Token::Assign | Token::PlusAssign | Token::MinusAssign => self.parse_assign(left)?,
             Token::LParen => self.parse_call_expression(left)?,
-            _ => panic!("in parse_led_expression"),
+            Token::LBracket => self.parse_index_expression(left)?,
+            _ => return Err(self.unexpected_token("infix operator")),
         };
 
         Ok(led_expression)
     }
 
-    fn parse_infix(&mut self, left: Box<ExpressionNode>) -> Result<Box<ExpressionNode>, String> {
-        let token = self.read_token().ok_or("None token".to_string())?;
+    fn parse_infix(
+        &mut self,
+        left: Box<ExpressionNode>,
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        let token = self.peek_token();
         let operator_type = match token {
             Token::Plus => InfixOperatorType::Plus,
             Token::Minus => InfixOperatorType::Minus,
             Token::Asterisk => InfixOperatorType::Asterisk,
             Token::Slash => InfixOperatorType::Slash,
+            Token::Percent => InfixOperatorType::Percent,
             Token::Eq => InfixOperatorType::Eq,
             Token::NotEq => InfixOperatorType::NotEq,
             Token::Gt => InfixOperatorType::Gt,
             Token::Lt => InfixOperatorType::Lt,
-            _ => panic!("in parse_infix"),
+            Token::GtEq => InfixOperatorType::GtEq,
+            Token::LtEq => InfixOperatorType::LtEq,
+            _ => return Err(self.unexpected_token("infix operator")),
         };
+        self.read_token();
         let right = self.parse_expression(to_binding_power(&token))?;
+        let span = left.span().to(self.last_span());
         Ok(Box::new(ExpressionNode::InfixOperator {
             operator_type,
             left,
             right,
+            span,
+        }))
+    }
+
+    fn parse_logical(
+        &mut self,
+        left: Box<ExpressionNode>,
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        let token = self.peek_token();
+        let operator_type = match token {
+            Token::And => LogicalOperatorType::And,
+            Token::Or => LogicalOperatorType::Or,
+            _ => return Err(self.unexpected_token("logical operator")),
+        };
+        self.read_token();
+        let right = self.parse_expression(to_binding_power(&token))?;
+        let span = left.span().to(self.last_span());
+        Ok(Box::new(ExpressionNode::LogicalOperator {
+            operator_type,
+            left,
+            right,
+            span,
+        }))
+    }
+
+    fn parse_assign(
+        &mut self,
+        target: Box<ExpressionNode>,
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        let operator_type = match self.peek_token() {
+            Token::Assign => AssignOperatorType::Assign,
+            Token::PlusAssign => AssignOperatorType::PlusAssign,
+            Token::MinusAssign => AssignOperatorType::MinusAssign,
+            _ => return Err(self.unexpected_token("assignment operator")),
+        };
+        if !is_assignment_target(&target) {
+            return Err(ParseError::new(
+                ParseErrorKind::InvalidAssignmentTarget,
+                target.span(),
+            ));
+        }
+        self.read_token();
+
+        // const への代入は実行しなくてもわかる. エラーを記録して読み進める
+        let root = assignment_root(&target);
+        if let ExpressionNode::Identifier { literal, span } = root {
+            let name = literal.iter().collect::<String>();
+            if self.lookup_mutable(&name) == Some(false) {
+                self.errors.push(ParseError::new(
+                    ParseErrorKind::AssignToConstant(name),
+                    *span,
+                ));
+            }
+        }
+
+        // `a = b = 1` は `a = (b = 1)` と右から結合する
+        let value = self.parse_expression(BindingPower::Lowest)?;
+        let span = target.span().to(self.last_span());
+        Ok(Box::new(ExpressionNode::Assign {
+            operator_type,
+            target,
+            value,
+            span,
         }))
     }
 
     fn parse_call_expression(
         &mut self,
         function: Box<ExpressionNode>,
-    ) -> Result<Box<ExpressionNode>, String> {
+    ) -> Result<Box<ExpressionNode>, ParseError> {
         // Token::LParen skip
         self.expect_token(Token::LParen)?;
         self.read_token();
 
         // arguments の読み込み
-        let mut arguments = Vec::new();
-        while !self.expect_token(Token::RParen).is_ok() {
-            let argument = self.parse_expression(BindingPower::LOWEST)?;
-            arguments.push(argument);
+        let arguments = self.parse_expression_list(Token::RParen)?;
+
+        // return
+        let span = function.span().to(self.last_span());
+        Ok(Box::new(ExpressionNode::CallExpression {
+            function,
+            arguments,
+            span,
+        }))
+    }
+
+    fn parse_array_literal(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+
+        // Token::LBracket skip
+        self.expect_token(Token::LBracket)?;
+        self.read_token();
+
+        let elements = self
+            .parse_expression_list(Token::RBracket)
+            .map_err(|error| error.with_note(start, "array opened here"))?;
+
+        Ok(Box::new(ExpressionNode::ArrayLiteral {
+            elements,
+            span: start.to(self.last_span()),
+        }))
+    }
+
+    fn parse_hash_literal(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+
+        // Token::LBrace skip
+        self.expect_token(Token::LBrace)?;
+        self.read_token();
+
+        let mut pairs = Vec::new();
+        while self.expect_token(Token::RBrace).is_err() {
+            let key = self.parse_expression(BindingPower::Lowest)?;
+
+            // Token::Colon skip
+            self.expect_token(Token::Colon)?;
+            self.read_token();
+
+            let value = self.parse_expression(BindingPower::Lowest)?;
+            pairs.push((key, value));
 
-            // Token::Comma が存在するならば skip
+            // 次の pair との間には Token::Comma が必要
             if self.expect_token(Token::Comma).is_ok() {
                 self.read_token();
+            } else {
+                self.expect_token(Token::RBrace)
+                    .map_err(|_| self.unexpected_token("Comma or RBrace"))?;
             }
         }
 
-        // Token::RParen skip
-        self.expect_token(Token::RParen)?;
+        // Token::RBrace skip
+        self.expect_token(Token::RBrace)
+            .map_err(|error| error.with_note(start, "hash opened here"))?;
         self.read_token();
 
-        // return
-        Ok(Box::new(ExpressionNode::CallExpression {
-            function,
-            arguments,
+        Ok(Box::new(ExpressionNode::HashLiteral {
+            pairs,
+            span: start.to(self.last_span()),
         }))
     }
 
-    fn parse_identifier(&mut self) -> Result<Box<ExpressionNode>, String> {
-        let token_opt = self.read_token();
-        match token_opt {
-            Some(Token::Ident(literal)) => Ok(Box::new(ExpressionNode::Identifier { literal })),
-            None => Err("not found token".to_string()),
-            _ => Err("non-expected token".to_string()),
+    fn parse_index_expression(
+        &mut self,
+        left: Box<ExpressionNode>,
+    ) -> Result<Box<ExpressionNode>, ParseError> {
+        // Token::LBracket skip
+        self.expect_token(Token::LBracket)?;
+        self.read_token();
+
+        let index = self.parse_expression(BindingPower::Lowest)?;
+
+        // Token::RBracket skip
+        self.expect_token(Token::RBracket)?;
+        self.read_token();
+
+        let span = left.span().to(self.last_span());
+        Ok(Box::new(ExpressionNode::IndexExpression {
+            left,
+            index,
+            span,
+        }))
+    }
+
+    // `,` 区切りの式を end まで読み, end も読み飛ばす
+    #[allow(clippy::vec_box)] // ast の arguments, elements に合わせる
+    fn parse_expression_list(
+        &mut self,
+        end: Token,
+    ) -> Result<Vec<Box<ExpressionNode>>, ParseError> {
+        let mut expressions = Vec::new();
+        while self.expect_token(end.clone()).is_err() {
+            let expression = self.parse_expression(BindingPower::Lowest)?;
+            expressions.push(expression);
+
+            // 次の要素との間には Token::Comma が必要
+            if self.expect_token(Token::Comma).is_ok() {
+                self.read_token();
+            } else {
+                self.expect_token(end.clone())
+                    .map_err(|_| self.unexpected_token(&format!("Comma or {:?}", end)))?;
+            }
+        }
+
+        // end skip
+        self.expect_token(end)?;
+        self.read_token();
+
+        Ok(expressions)
+    }
+
+    fn parse_identifier(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+        match self.peek_token() {
+            Token::Ident(literal) => {
+                self.read_token();
+                Ok(Box::new(ExpressionNode::Identifier {
+                    literal,
+                    span: start,
+                }))
+            }
+            _ => Err(self.unexpected_token("identifier")),
         }
     }
 
-    fn parse_integer(&mut self) -> Result<Box<ExpressionNode>, String> {
-        let token_opt = self.read_token();
-        match token_opt {
-            Some(Token::Int(literal)) => Ok(Box::new(ExpressionNode::Integer { literal })),
-            None => Err("not found token".to_string()),
-            _ => Err("non-expected token".to_string()),
+    fn parse_integer(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+        match self.peek_token() {
+            Token::Int(value) => {
+                self.read_token();
+                Ok(Box::new(match value.to_i64() {
+                    Some(value) => ExpressionNode::Integer { value, span: start },
+                    None => ExpressionNode::BigInteger { value, span: start },
+                }))
+            }
+            _ => Err(self.unexpected_token("integer")),
         }
     }
 
-    fn parse_boolean(&mut self) -> Result<Box<ExpressionNode>, String> {
-        let token = self.read_token().ok_or("None token".to_string())?;
-        match token {
-            Token::True => Ok(Box::new(ExpressionNode::Boolean {
-                boolean_type: BooleanType::True,
-            })),
-            Token::False => Ok(Box::new(ExpressionNode::Boolean {
-                boolean_type: BooleanType::False,
-            })),
-            _ => Err("in parse_boolean".to_string()),
+    fn parse_float(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+        match self.peek_token() {
+            Token::Float(literal) => {
+                self.read_token();
+                Ok(Box::new(ExpressionNode::Float {
+                    literal,
+                    span: start,
+                }))
+            }
+            _ => Err(self.unexpected_token("float")),
         }
     }
+
+    fn parse_string(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+        match self.peek_token() {
+            Token::Str(literal) => {
+                self.read_token();
+                Ok(Box::new(ExpressionNode::StringLiteral {
+                    literal,
+                    span: start,
+                }))
+            }
+            _ => Err(self.unexpected_token("string")),
+        }
+    }
+
+    fn parse_boolean(&mut self) -> Result<Box<ExpressionNode>, ParseError> {
+        let start = self.peek_span();
+        let boolean_type = match self.peek_token() {
+            Token::True => BooleanType::True,
+            Token::False => BooleanType::False,
+            _ => return Err(self.unexpected_token("boolean")),
+        };
+        self.read_token();
+        Ok(Box::new(ExpressionNode::Boolean {
+            boolean_type,
+            span: start,
+        }))
+    }
 }
 
 #[derive(PartialOrd, PartialEq)]
 enum BindingPower {
-    END,
-    LOWEST,
-    EQUALS,
-    LESSGREATER,
-    SUM,
-    PRODUCT,
-    PREFIX,
-    CALL,
+    End,
+    Lowest,
+    Assign,
+    LogicalOr,
+    LogicalAnd,
+    Equals,
+    LessGreater,
+    Sum,
+    Product,
+    Prefix,
+    Call,
+    Index,
 }
 
 fn to_binding_power(token: &Token) -> BindingPower {
     match token {
-        Token::Eq | Token::NotEq => BindingPower::EQUALS,
-        Token::Lt | Token::Gt => BindingPower::LESSGREATER,
-        Token::Plus | Token::Minus => BindingPower::SUM,
-        Token::Asterisk | Token::Slash => BindingPower::PRODUCT,
-        Token::LParen => BindingPower::CALL,
-        _ => BindingPower::END,
+        Token::Assign | Token::PlusAssign | Token::MinusAssign => BindingPower::Assign,
+        Token::Or => BindingPower::LogicalOr,
+        Token::And => BindingPower::LogicalAnd,
+        Token::Eq | Token::NotEq => BindingPower::Equals,
+        Token::Lt | Token::Gt | Token::LtEq | Token::GtEq => BindingPower::LessGreater,
+        Token::Plus | Token::Minus => BindingPower::Sum,
+        Token::Asterisk | Token::Slash | Token::Percent => BindingPower::Product,
+        Token::LParen => BindingPower::Call,
+        Token::LBracket => BindingPower::Index,
+        _ => BindingPower::End,
+    }
+}
+
+// 代入できるのは変数と, 変数から始まる index 式 (e.g. `a`, `a[0]`, `a["k"][1]`)
+fn is_assignment_target(expression: &ExpressionNode) -> bool {
+    match expression {
+        ExpressionNode::Identifier { .. } => true,
+        ExpressionNode::IndexExpression { left, .. } => is_assignment_target(left),
+        _ => false,
+    }
+}
+
+// 代入先の index 式をたどった先の変数 (e.g. `a["k"][1]` の `a`)
+fn assignment_root(expression: &ExpressionNode) -> &ExpressionNode {
+    match expression {
+        ExpressionNode::IndexExpression { left, .. } => assignment_root(left),
+        _ => expression,
     }
 }
 
-pub fn parse(token_vec: Vec<Token>) -> Result<Program, String> {
+pub fn parse(token_vec: Vec<SpannedToken>) -> Result<Program, Vec<ParseError>> {
+    let (program, errors) = parse_partial(token_vec);
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
+
+// エラーがあっても最後まで読み進め, 読めた部分の Program とすべてのエラーを返す
+pub fn parse_partial(token_vec: Vec<SpannedToken>) -> (Program, Vec<ParseError>) {
     let mut parser = Parser::new(token_vec);
     let mut program = Program::new();
-    while !parser.expect_token(Token::Eof).is_ok() {
-        let statement = parser.parse_statement()?;
-        program.add_statement(statement);
+    while parser.expect_token(Token::Eof).is_err() {
+        match parser.parse_statement() {
+            Ok(statement) => program.add_statement(statement),
+            Err(error) => {
+                parser.errors.push(error);
+                parser.synchronize();
+                // トップレベルの余分な `}` は読み捨てる
+                if parser.expect_token(Token::RBrace).is_ok() {
+                    parser.read_token();
+                }
+            }
+        }
     }
-    Ok(program)
+    (program, parser.errors)
 }
 
 #[cfg(test)]
@@ -418,7 +1060,7 @@ mod test {
 
     fn test_parse(expect_strings: Vec<&str>, test_strings: Vec<&str>) {
         for (&test_string, &expect_sting) in test_strings.iter().zip(expect_strings.iter()) {
-            let program = parse(crate::lexer::lex(test_string)).unwrap();
+            let program = parse(crate::lexer::lex(test_string).unwrap()).unwrap();
             let actual_string = &program.literal();
             assert_eq!(expect_sting, actual_string);
         }
@@ -447,10 +1089,33 @@ mod test {
         test_parse(expect_strings, test_strings);
     }
 
+    #[test]
+    fn test_string_exprssions() {
+        let test_strings = vec![r#""hello world";"#, r#""a" + "b""#, r#""say \"hi\"\n""#];
+        let expect_strings = vec![r#""hello world";"#, r#"("a" + "b");"#, r#""say \"hi\"\n";"#];
+
+        test_parse(expect_strings, test_strings);
+    }
+
     #[test]
     fn test_integer_exprssions() {
-        let test_strings = vec!["5;", "5"];
-        let expect_strings = vec!["5;", "5;"];
+        // literal は parse した 10 進数の値で表示される
+        let test_strings = vec![
+            "5;",
+            "5",
+            "0xFF;",
+            "0b1_0000 + 1_000",
+            "99_999_999_999_999_999_999",
+        ];
+        let expect_strings = vec!["5;", "5;", "255;", "(16 + 1000);", "99999999999999999999;"];
+
+        test_parse(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_float_exprssions() {
+        let test_strings = vec!["3.14;", ".5 * 2", "-1e-9;"];
+        let expect_strings = vec!["3.14;", "(.5 * 2);", "(-1e-9);"];
 
         test_parse(expect_strings, test_strings);
     }
@@ -467,6 +1132,7 @@ mod test {
     fn test_infix_exprssions() {
         let test_strings = vec![
             "5 + 5;", "5 - 5;", "5 * 5;", "5 / 5;", "5 > 5;", "5 < 5;", "5 == 5;", "5 != 5;",
+            "5 % 5;", "5 >= 5;", "5 <= 5;", "a && b;", "a || b;",
         ];
         let expect_strings = vec![
             "(5 + 5);",
@@ -477,6 +1143,11 @@ mod test {
             "(5 < 5);",
             "(5 == 5);",
             "(5 != 5);",
+            "(5 % 5);",
+            "(5 >= 5);",
+            "(5 <= 5);",
+            "(a && b);",
+            "(a || b);",
         ];
 
         test_parse(expect_strings, test_strings);
@@ -496,6 +1167,14 @@ mod test {
             "5 > 4 == 3 < 4;",
             "5 < 4 != 3 > 4;",
             "3 + 4 * 5 == 3 * 1 + 4 * 5;",
+            "a * [1, 2, 3, 4][b * c] * d;",
+            "add(a * b[2], b[1], 2 * [1, 2][1]);",
+            "-a[0];",
+            "a + b % c * d;",
+            "a <= b == c >= d;",
+            "a || b && c || d;",
+            "a == b && c != d;",
+            "!a && -b < c || d;",
         ];
         let expect_strings = vec![
             "((-a) + b);",
@@ -509,9 +1188,79 @@ mod test {
             "((5 > 4) == (3 < 4));",
             "((5 < 4) != (3 > 4));",
             "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)));",
+            "((a * ([1, 2, 3, 4][(b * c)])) * d);",
+            "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])));",
+            "(-(a[0]));",
+            "(a + ((b % c) * d));",
+            "((a <= b) == (c >= d));",
+            "((a || (b && c)) || d);",
+            "((a == b) && (c != d));",
+            "(((!a) && ((-b) < c)) || d);",
+        ];
+
+        test_parse(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_array_literal() {
+        let test_strings = vec![
+            "[1, 2 * 2, 3 + 3];",
+            "[];",
+            "[[1], \"a\"][0][0];",
+            "[1, 2,];",
+        ];
+        let expect_strings = vec![
+            "[1, (2 * 2), (3 + 3)];",
+            "[];",
+            "(([[1], \"a\"][0])[0]);",
+            "[1, 2];",
         ];
 
         test_parse(expect_strings, test_strings);
+
+        // 要素の間の Comma は省略できない
+        let test_strings = vec!["[1 2]", "f(1 2)", "fn(x y) { x }"];
+        let expect_errors = vec![
+            "1:4: expected Comma or RBracket, found Int(2)",
+            "1:5: expected Comma or RParen, found Int(2)",
+            "1:6: expected Comma or RParen, found Ident(['y'])",
+        ];
+        for (test_string, expect_error) in test_strings.into_iter().zip(expect_errors) {
+            let errors = parse(crate::lexer::lex(test_string).unwrap())
+                .err()
+                .unwrap();
+            assert_eq!(errors[0].to_string(), expect_error, "{}", test_string);
+        }
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let test_strings = vec![
+            r#"{"one": 1, "two": 2};"#,
+            "{};",
+            "{1 + 1: 2 * 2, true: [1],}[2];",
+            r#"let f = fn() { {"a": 1} };"#,
+        ];
+        let expect_strings = vec![
+            r#"{"one": 1, "two": 2};"#,
+            "{};",
+            "({(1 + 1): (2 * 2), true: [1]}[2]);",
+            r#"let f = fn(){ {"a": 1}; };"#,
+        ];
+
+        test_parse(expect_strings, test_strings);
+
+        let errors = parse(crate::lexer::lex(r#"{"a": 1 "b": 2}"#).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "1:9: expected Comma or RBrace, found Str(['b'])"
+        );
+        let errors = parse(crate::lexer::lex(r#"{"a" 1}"#).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(errors[0].to_string(), "1:6: expected Colon, found Int(1)");
     }
 
     #[test]
@@ -535,6 +1284,239 @@ mod test {
         test_parse(expect_strings, test_strings);
     }
 
+    #[test]
+    fn test_statement_after_block() {
+        // block の後ろの `;` は文の終わりで, 次の `[` や `(` は新しい文になる
+        let test_strings = vec![
+            "let f = fn() { 1 }; [1, 2]",
+            "let f = fn() { 1 };\n[1, 2]",
+            "if (true) { [9] }; [0]",
+            "let f = fn() { 1 }; (1 + 2) * 3",
+            "if (x) { y }; (z)",
+            "while (x) { y }; [z]",
+        ];
+        let expect_strings = vec![
+            "let f = fn(){ 1; };[1, 2];",
+            "let f = fn(){ 1; };[1, 2];",
+            "if true { [9]; };[0];",
+            "let f = fn(){ 1; };((1 + 2) * 3);",
+            "if x { y; };z;",
+            "while x { y; }[z];",
+        ];
+
+        test_parse(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let test_strings = vec![
+            "while (i < 10) { let i = i + 1; }",
+            "while (true) { if (done) { break; } continue };",
+            "while (a) { while (b) { break } let f = fn() { 1 }; continue }",
+        ];
+        let expect_strings = vec![
+            "while (i < 10) { let i = (i + 1); }",
+            "while true { if done { break; }; continue; }",
+            "while a { while b { break; } let f = fn(){ 1; }; continue; }",
+        ];
+
+        test_parse(expect_strings, test_strings);
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let test_strings = vec![
+            "for (x in xs) { puts(x); }",
+            "for (k, v in {\"a\": 1}) { if (v > 0) { continue } break };",
+            "for (i in range(1 + 2)) { for (c in \"ab\") { c } }",
+        ];
+        let expect_strings = vec![
+            "for x in xs { puts(x); }",
+            "for k, v in {\"a\": 1} { if (v > 0) { continue; }; break; }",
+            "for i in range((1 + 2)) { for c in \"ab\" { c; } }",
+        ];
+
+        test_parse(expect_strings, test_strings);
+
+        let test_strings = vec![
+            "for (x xs) { x }",
+            "for (1 in xs) { x }",
+            "for (a, b, c in xs) { a }",
+            "for (x in xs) x",
+        ];
+        for test_string in test_strings {
+            let result = parse(crate::lexer::lex(test_string).unwrap());
+            assert!(result.is_err(), "{}", test_string);
+        }
+    }
+
+    #[test]
+    fn test_assign_expressions() {
+        let test_strings = vec![
+            "x = 5;",
+            "x += 1 * 2",
+            "x -= y || z",
+            "a = b = c",
+            "a[0] = 1",
+            "h[\"k\"][i + 1] += 2",
+            "let y = x = 1;",
+        ];
+        let expect_strings = vec![
+            "(x = 5);",
+            "(x += (1 * 2));",
+            "(x -= (y || z));",
+            "(a = (b = c));",
+            "((a[0]) = 1);",
+            "(((h[\"k\"])[(i + 1)]) += 2);",
+            "let y = (x = 1);",
+        ];
+
+        test_parse(expect_strings, test_strings);
+
+        let test_strings = vec![
+            "1 = 2",
+            "f() = 1",
+            "a + b = 1",
+            "-x = 1",
+            "x = 1 = 2",
+            "f()[0] = 1",
+        ];
+        let expect_columns = vec![1, 1, 1, 1, 5, 1];
+        for (test_string, column) in test_strings.into_iter().zip(expect_columns) {
+            let error = parse(crate::lexer::lex(test_string).unwrap())
+                .err()
+                .unwrap()
+                .remove(0);
+            assert_eq!(
+                error.kind,
+                ParseErrorKind::InvalidAssignmentTarget,
+                "{}",
+                test_string
+            );
+            assert_eq!(error.span.start.column, column, "{}", test_string);
+        }
+    }
+
+    #[test]
+    fn test_const_statements() {
+        let test_strings = vec!["const x = 5;", "const f = fn(a) { a = 1 };"];
+        let expect_strings = vec!["const x = 5;", "const f = fn(a){ (a = 1); };"];
+
+        test_parse(expect_strings, test_strings);
+
+        let test_strings = vec![
+            "const x = 1; x = 2",
+            "const a = [1]; a[0] += 2",
+            "const x = 1; let f = fn() { x -= 1 };",
+            "const x = 1; let x = 2;",
+            "const x = 1; if (true) { const x = 2 }",
+            "for (i in xs) { const c = i; c = 0 }",
+            "if (x) { const c = 1; c = 2 }",
+            "if (x) { const c = 1 } else { const c = 2 }; c = 3",
+        ];
+        let expect_errors = vec![
+            "1:14: cannot assign to constant: x",
+            "1:16: cannot assign to constant: a",
+            "1:29: cannot assign to constant: x",
+            "1:18: cannot redeclare constant: x",
+            "1:32: cannot redeclare constant: x",
+            "1:30: cannot assign to constant: c",
+            "1:23: cannot assign to constant: c",
+            "1:46: cannot assign to constant: c",
+        ];
+        for (test_string, expect_error) in test_strings.into_iter().zip(expect_errors) {
+            let errors = parse(crate::lexer::lex(test_string).unwrap())
+                .err()
+                .unwrap();
+            assert_eq!(errors.len(), 1, "{}", test_string);
+            assert_eq!(errors[0].to_string(), expect_error);
+        }
+
+        // 関数の引数や中の宣言, loop の body の宣言は外側の const を隠す
+        let test_strings = vec![
+            "const x = 1; let f = fn(x) { x = 2 };",
+            "const x = 1; let f = fn() { let x = 2; x = 3 };",
+            "const x = 1; for (x in xs) { x = 2 }",
+            "while (true) { const c = 1; } const c = 2;",
+            // if と else の分岐は片方しか実行されない
+            "let pick = fn(flag) { if (flag) { const limit = 1; limit } else { const limit = 2; limit } }; pick(false)",
+            "if (x == 0) { const y = 1 } else { let y = 2; y = 3 }",
+            // 片方の分岐でだけ const なら, 実行時にしか決まらない
+            "if (x) { const c = 1 }; c = 2",
+            "if (x) { const c = 1 } else { let c = 2 }; let c = 3",
+        ];
+        for test_string in test_strings {
+            assert!(
+                parse(crate::lexer::lex(test_string).unwrap()).is_ok(),
+                "{}",
+                test_string
+            );
+        }
+    }
+
+    #[test]
+    fn test_loop_control_in_expression() {
+        let test_strings = vec![
+            "let i = 0; while (true) { i += 1; let x = if (i > 3) { break }; } i",
+            "while (true) { puts(if (true) { break }) }",
+            "for (x in [1]) { r = push(r, if (true) { continue }) }",
+            "while (true) { [if (true) { if (true) { break } }] }",
+            "while (true) { if (true) { break } + 1 }",
+        ];
+        let expect_errors = vec![
+            "1:56: `break` inside an `if` used as a value",
+            "1:33: `break` inside an `if` used as a value",
+            "1:42: `continue` inside an `if` used as a value",
+            "1:41: `break` inside an `if` used as a value",
+            "1:28: `break` inside an `if` used as a value",
+        ];
+
+        for (test_string, expect_error) in test_strings.into_iter().zip(expect_errors) {
+            let errors = parse(crate::lexer::lex(test_string).unwrap())
+                .err()
+                .unwrap();
+            assert_eq!(errors.len(), 1, "{}", test_string);
+            assert_eq!(errors[0].to_string(), expect_error);
+        }
+
+        // 文の先頭の if や, 値の if の中の loop では使える
+        let test_strings = vec![
+            "while (true) { if (true) { break } else { continue } }",
+            "while (true) { if (true) { 1 } + 1; break }",
+            "let x = if (true) { while (true) { break } }",
+            "while (true) { let f = if (true) { fn() { for (x in []) { continue } } }; break }",
+        ];
+        for test_string in test_strings {
+            assert!(
+                parse(crate::lexer::lex(test_string).unwrap()).is_ok(),
+                "{}",
+                test_string
+            );
+        }
+    }
+
+    #[test]
+    fn test_loop_control_outside_loop() {
+        let test_strings = vec![
+            "break;",
+            "if (x) { continue }",
+            "while (x) { let f = fn() { break; }; }",
+        ];
+        let expect_errors = vec![
+            "1:1: `break` outside of a loop",
+            "1:10: `continue` outside of a loop",
+            "1:28: `break` outside of a loop",
+        ];
+
+        for (test_string, expect_error) in test_strings.into_iter().zip(expect_errors) {
+            let errors = parse(crate::lexer::lex(test_string).unwrap())
+                .err()
+                .unwrap();
+            assert_eq!(errors.len(), 1, "{}", test_string);
+            assert_eq!(errors[0].to_string(), expect_error);
+        }
+    }
+
     #[test]
     fn test_function_literal() {
         let test_strings = vec!["fn(x, y){ x + y; };", "fn(){ 5 };"];
@@ -542,6 +1524,148 @@ mod test {
 
         test_parse(expect_strings, test_strings);
     }
+    #[test]
+    fn test_spans() {
+        let program = parse(crate::lexer::lex("let x = 5;\nadd(x,\n  y * 2);").unwrap()).unwrap();
+        let statements: Vec<&StatementNode> = program.statement_iter().collect();
+
+        let span = statements[0].span();
+        assert_eq!((span.start.line, span.start.column), (1, 1));
+        assert_eq!((span.end.line, span.end.column), (1, 11));
+
+        let span = statements[1].span();
+        assert_eq!((span.start.offset, span.end.offset), (11, 27));
+        match statements[1] {
+            StatementNode::ExpressionStatement { expression, .. } => {
+                let span = expression.span();
+                assert_eq!((span.start.line, span.start.column), (2, 1));
+                assert_eq!((span.end.line, span.end.column), (3, 9));
+                match expression.as_ref() {
+                    ExpressionNode::CallExpression { arguments, .. } => {
+                        let span = arguments[1].span();
+                        assert_eq!((span.start.line, span.start.column), (3, 3));
+                        assert_eq!((span.end.line, span.end.column), (3, 8));
+                    }
+                    _ => panic!("not a call expression"),
+                }
+            }
+            _ => panic!("not an expression statement"),
+        }
+    }
+
+    #[test]
+    fn test_error_position() {
+        let error = parse(crate::lexer::lex("let x = (1 +\n 2;").unwrap())
+            .err()
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::UnexpectedToken {
+                expected: "RParen".to_string(),
+                actual: Token::SemiColon
+            }
+        );
+        assert_eq!(error.to_string(), "2:3: expected RParen, found SemiColon");
+
+        let error = parse(crate::lexer::lex("let = 5;").unwrap())
+            .err()
+            .unwrap()
+            .remove(0);
+        assert_eq!(error.to_string(), "1:5: expected identifier, found Assign");
+
+        let error = parse(crate::lexer::lex("if (x) { x").unwrap())
+            .err()
+            .unwrap()
+            .remove(0);
+        assert_eq!(error.to_string(), "1:11: expected RBrace, found Eof");
+    }
+
+    #[test]
+    fn test_malformed_programs() {
+        let test_strings = vec![
+            ")",
+            "let x = ;",
+            "let 5 = 5;",
+            "+ 1",
+            "1 +",
+            "fn(1) { 1 }",
+            "fn(x { x }",
+            "if x { x }",
+            "if (x) { x",
+            "add(1, 2",
+            "(1 + 2",
+            "!",
+            "return",
+        ];
+
+        for test_string in test_strings {
+            let result = parse(crate::lexer::lex(test_string).unwrap());
+            assert!(result.is_err(), "{}", test_string);
+        }
+    }
+
+    #[test]
+    fn test_nesting_too_deep() {
+        let nested = format!("{}1{}", "(".repeat(300), ")".repeat(300));
+        let prefixed = format!("{}1", "-".repeat(300));
+
+        // debug build では上限に達するまでに test thread の stack では足りない
+        let handle = std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(move || {
+                for test_string in [nested, prefixed] {
+                    let error = parse(crate::lexer::lex(&test_string).unwrap())
+                        .err()
+                        .unwrap()
+                        .remove(0);
+                    assert_eq!(error.kind, ParseErrorKind::NestingTooDeep);
+                }
+
+                // 左結合の演算子を並べても入れ子にはならない
+                let chained = vec!["1"; 1000].join(" + ");
+                assert!(parse(crate::lexer::lex(&chained).unwrap()).is_ok());
+            })
+            .unwrap();
+        assert!(handle.join().is_ok());
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let test_string = "let x = ;
+        let y = 5;
+        let f = fn(a) {
+            let b = a +;
+            return b;
+        };
+        let = 10;
+        y + 1;";
+        let (program, errors) = parse_partial(crate::lexer::lex(test_string).unwrap());
+
+        let error_lines: Vec<u32> = errors.iter().map(|e| e.span.start.line).collect();
+        assert_eq!(error_lines, vec![1, 4, 7]);
+        assert_eq!(
+            program.literal(),
+            "let y = 5;let f = fn(a){ return b; };(y + 1);"
+        );
+        assert_eq!(
+            parse(crate::lexer::lex(test_string).unwrap())
+                .err()
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_error_recovery_stray_brace() {
+        let (program, errors) =
+            parse_partial(crate::lexer::lex("} 1; ) 2; if (x) { (; 3 } 4").unwrap());
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(program.literal(), "1;if x { 3; };4;");
+    }
+
     #[test]
     fn test_call_expression() {
         let test_strings = vec![
@@ -0,0 +1,248 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use monkey_by_rust::diagnostic;
+use monkey_by_rust::error::{Error, LexErrorKind};
+use monkey_by_rust::lexer;
+use monkey_by_rust::token::{Position, Token};
+use monkey_by_rust::Interpreter;
+
+const PROMPT: &str = ">> ";
+const CONTINUATION_PROMPT: &str = ".. ";
+const SOURCE_NAME: &str = "<repl>";
+
+const HELP: &str = ":help          show this message
+:env           list bindings in the global environment
+:reset         discard all bindings
+:load <file>   evaluate a file in the current environment
+:history       list previously entered inputs (no arrow-key recall)
+:quit          exit the REPL";
+
+enum Command {
+    Continue,
+    Quit,
+}
+
+pub struct Repl {
+    // run や -e と同じ Interpreter を通して評価する
+    interpreter: Interpreter,
+    history: Vec<String>,
+    // これまでに評価した入力をつなげたもの. 以前の入力で定義した関数を
+    // エラーの note で指せるよう, 位置は入力をまたいで通しで数える
+    source: String,
+    end: Position,
+    color: bool,
+}
+
+impl Repl {
+    pub fn new(color: bool) -> Self {
+        Repl {
+            interpreter: crate::new_interpreter(),
+            history: Vec::new(),
+            source: String::new(),
+            end: Position::default(),
+            color,
+        }
+    }
+
+    pub fn run<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> io::Result<()> {
+        let mut buffer = String::new();
+        loop {
+            let prompt = if buffer.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION_PROMPT
+            };
+            write!(output, "{}", prompt)?;
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                // EOF: 入力途中のものがあれば評価してから終了
+                if !buffer.trim().is_empty() {
+                    writeln!(output)?;
+                    self.eval_input(&buffer, &mut output)?;
+                }
+                writeln!(output)?;
+                return Ok(());
+            }
+
+            // meta command は入力の先頭でのみ受け付ける
+            if buffer.is_empty() && line.trim_start().starts_with(':') {
+                match self.run_command(line.trim(), &mut output)? {
+                    Command::Continue => continue,
+                    Command::Quit => return Ok(()),
+                }
+            }
+
+            buffer.push_str(&line);
+            if is_incomplete(&buffer) {
+                continue;
+            }
+
+            let source = std::mem::take(&mut buffer);
+            if source.trim().is_empty() {
+                continue;
+            }
+            self.eval_input(&source, &mut output)?;
+        }
+    }
+
+    fn eval_input<W: Write>(&mut self, source: &str, output: &mut W) -> io::Result<()> {
+        self.history.push(source.trim_end().to_string());
+        self.eval_source(source, output)
+    }
+
+    fn eval_source<W: Write>(&mut self, source: &str, output: &mut W) -> io::Result<()> {
+        let start = self.end;
+        self.source.push_str(source);
+        for c in source.chars() {
+            self.end.advance(c);
+        }
+        // 入力が改行で終わっていなくても, 次の入力は新しい行から始める
+        if !source.ends_with('\n') {
+            self.source.push('\n');
+            self.end.advance('\n');
+        }
+
+        match self.interpreter.eval_str_from(source, start) {
+            Ok(object) => writeln!(output, "{}", object.literal()),
+            Err(error) => self.report(error, output),
+        }
+    }
+
+    fn report<W: Write>(&self, error: Error, output: &mut W) -> io::Result<()> {
+        let rendered = diagnostic::render_error(&error, &self.source, SOURCE_NAME, self.color);
+        write!(output, "{}", rendered)
+    }
+
+    fn run_command<W: Write>(&mut self, line: &str, output: &mut W) -> io::Result<Command> {
+        let mut words = line.splitn(2, char::is_whitespace);
+        let command = words.next().unwrap_or("");
+        let argument = words.next().map(str::trim).unwrap_or("");
+
+        match command {
+            ":help" => writeln!(output, "{}", HELP)?,
+            ":env" => {
+                for name in self.interpreter.global_names() {
+                    let keyword = match self.interpreter.is_global_mutable(&name) {
+                        Some(false) => "const ",
+                        _ => "",
+                    };
+                    let literal = self
+                        .interpreter
+                        .get_global(&name)
+                        .map(|object| object.literal())
+                        .unwrap_or_default();
+                    writeln!(output, "{}{} = {}", keyword, name, literal)?;
+                }
+            }
+            ":reset" => {
+                self.interpreter.reset();
+                writeln!(output, "environment cleared")?;
+            }
+            ":load" => {
+                if argument.is_empty() {
+                    writeln!(output, "usage: :load <file>")?;
+                } else {
+                    match fs::read_to_string(argument) {
+                        Ok(source) => self.eval_source(&source, output)?,
+                        Err(error) => writeln!(output, "cannot read {}: {}", argument, error)?,
+                    }
+                }
+            }
+            ":history" => {
+                for (index, entry) in self.history.iter().enumerate() {
+                    writeln!(output, "{:>4}  {}", index + 1, entry)?;
+                }
+            }
+            ":quit" | ":q" => return Ok(Command::Quit),
+            _ => writeln!(output, "unknown command: {} (try :help)", command)?,
+        }
+        Ok(Command::Continue)
+    }
+}
+
+// 括弧が閉じられていなければ, 続きの行を待つ
+fn is_incomplete(source: &str) -> bool {
+    // 閉じていない文字列と block comment は続きの行を待ち, それ以外の lex できない入力はすぐにエラーを表示する
+    let tokens = match lexer::lex(source) {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            return matches!(
+                error.kind,
+                LexErrorKind::UnterminatedString | LexErrorKind::UnterminatedComment
+            )
+        }
+    };
+
+    let mut depth = 0;
+    for spanned_token in tokens {
+        match spanned_token.token {
+            Token::LBrace | Token::LParen | Token::LBracket => depth += 1,
+            Token::RBrace | Token::RParen | Token::RBracket => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run_repl(input: &str) -> String {
+        let mut output = Vec::new();
+        Repl::new(false).run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(is_incomplete("let f = fn(x) {"));
+        assert!(is_incomplete("add(1,"));
+        assert!(is_incomplete("let a = [1,"));
+        assert!(!is_incomplete("let f = fn(x) { x };"));
+        assert!(!is_incomplete("}"));
+        assert!(is_incomplete("let s = \"abc"));
+        assert!(!is_incomplete("let s = \"a{\";"));
+        assert!(is_incomplete("let a = 1; /* note"));
+        assert!(!is_incomplete("let a = 1; // {"));
+    }
+
+    #[test]
+    fn test_env_persists_across_lines() {
+        let output = run_repl("let a = 5;\nlet b = a * 2;\nb + 1\n");
+        assert!(output.contains("11\n"));
+    }
+
+    #[test]
+    fn test_multi_line_input() {
+        let output = run_repl("let add = fn(x, y) {\n  x + y\n};\nadd(1, 2)\n");
+        assert!(output.contains(CONTINUATION_PROMPT));
+        assert!(output.contains("3\n"));
+    }
+
+    #[test]
+    fn test_meta_commands() {
+        let output = run_repl("let a = 1;\n:env\n:reset\n:env\n:history\n:quit\n");
+        assert_eq!(output.matches("a = 1\n").count(), 1);
+        assert!(output.contains("environment cleared"));
+        assert!(output.contains("   1  let a = 1;"));
+    }
+
+    #[test]
+    fn test_env_lists_consts() {
+        let output = run_repl("let b = 2;\nconst a = [1];\n:env\n");
+        assert!(output.contains("const a = [1]\nb = 2\n"));
+    }
+
+    #[test]
+    fn test_error_points_into_earlier_input() {
+        let output = run_repl("let add = fn(x, y) { x + y };\nadd(1)\n");
+        assert!(output.contains(" --> <repl>:2:1\n"));
+        assert!(output.contains("2 | add(1)\n  | ^^^^^^\n"));
+        assert!(output.contains(" ::: <repl>:1:11\n"));
+        assert!(output.contains("function defined here with 2 parameters"));
+    }
+}
@@ -0,0 +1,24 @@
+use monkey_by_rust::error::Error;
+use monkey_by_rust::object::Object;
+
+pub fn run_source(source: &str) -> Result<Object, Error> {
+    crate::new_interpreter().eval_str(source)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_run_source() {
+        let result = run_source("let add = fn(x, y) { x + y }; add(1, 2);");
+        assert_eq!(result.ok().unwrap().literal(), "3");
+    }
+
+    #[test]
+    fn test_run_source_errors() {
+        assert!(matches!(run_source("let a = 1 @ 2;"), Err(Error::Lex(_))));
+        assert!(matches!(run_source("let = 5;"), Err(Error::Parse(_))));
+        assert!(matches!(run_source("foo;"), Err(Error::Runtime(_))));
+    }
+}
@@ -1,54 +1,153 @@
+use crate::bigint::BigInt;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
-    Illegal,
     Eof,
 
     Ident(Vec<char>),
-    Int(Vec<char>),
+    // 0x, 0o, 0b や桁区切りの `_` を読んだ後の値
+    Int(BigInt),
+    Float(Vec<char>),
+    // escape sequence を展開した後の文字列
+    Str(Vec<char>),
 
     Plus,
     Minus,
     Asterisk,
     Slash,
+    Percent,
 
     Bang,
     Assign,
+    PlusAssign,
+    MinusAssign,
     Eq,
     NotEq,
 
     Lt,
     Gt,
+    LtEq,
+    GtEq,
+
+    And,
+    Or,
 
     Comma,
     SemiColon,
+    Colon,
 
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 
     // keyword
     Function,
     Let,
+    Const,
     Return,
     True,
     False,
     If,
     Else,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
 }
 
 impl Token {
-    pub fn lookup_keyword(literal: &Vec<char>) -> Option<Self> {
+    pub fn lookup_keyword(literal: &[char]) -> Option<Self> {
         let literal_str: &str = &literal.iter().collect::<String>();
         match literal_str {
             "fn" => Some(Token::Function),
             "let" => Some(Token::Let),
+            "const" => Some(Token::Const),
             "return" => Some(Token::Return),
             "true" => Some(Token::True),
             "false" => Some(Token::False),
             "if" => Some(Token::If),
             "else" => Some(Token::Else),
+            "while" => Some(Token::While),
+            "for" => Some(Token::For),
+            "in" => Some(Token::In),
+            "break" => Some(Token::Break),
+            "continue" => Some(Token::Continue),
             _ => None,
         }
     }
 }
+
+// ソースコード上の位置. line, column は 1 始まり, offset は byte 単位
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Position {
+    // 文字 c を読み進めた後の位置に更新する
+    pub fn advance(&mut self, c: char) {
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+// [start, end) の範囲
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    // self の先頭から other の末尾までを覆う Span
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+// `// ...` や `/* ... */` の comment. text は区切りの記号も含む元の文字列
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}